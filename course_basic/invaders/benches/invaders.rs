@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use invaders::{config::GameConfig, invaders::Invaders};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// Big enough for the difference to show: close to 10000 invaders
+const NUM_COLS: usize = 400;
+const NUM_ROWS: usize = 200;
+const NUM_SHOTS: usize = 500;
+
+// What `Invaders` used to do, a plain list scanned for every lookup
+fn linear_army(invaders: &Invaders) -> Vec<(usize, usize, u32)> {
+    invaders.iter().map(|invader| (invader.x(), invader.y(), invader.points())).collect()
+}
+
+fn linear_kill_at(army: &mut Vec<(usize, usize, u32)>, x: usize, y: usize) -> Option<u32> {
+    let idx = army.iter().position(|(ix, iy, _)| *ix == x && *iy == y)?;
+    Some(army.remove(idx).2)
+}
+
+// Shots spread over the formation's rows, about half of them on an invader
+fn shots(config: &GameConfig) -> Vec<(usize, usize)> {
+    let mut rng = StdRng::seed_from_u64(17);
+    (0..NUM_SHOTS)
+        .map(|_| (rng.gen_range(0..config.num_cols), rng.gen_range(0..config.army_bottom())))
+        .collect()
+}
+
+pub fn hit_test_benchmark(c: &mut Criterion) {
+    let config = GameConfig::new(NUM_COLS, NUM_ROWS);
+    let shots = shots(&config);
+    let mut group = c.benchmark_group(format!("{} shots on a {}x{} board", NUM_SHOTS, NUM_COLS, NUM_ROWS));
+
+    group.bench_function("linear scan", |b| {
+        b.iter_batched(
+            || linear_army(&Invaders::new(&config)),
+            |mut army| {
+                for (x, y) in shots.iter() {
+                    black_box(linear_kill_at(&mut army, *x, *y));
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("occupancy grid", |b| {
+        b.iter_batched(
+            || Invaders::new(&config),
+            |mut invaders| {
+                for (x, y) in shots.iter() {
+                    black_box(invaders.kill_invader_at(*x, *y));
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+pub fn move_benchmark(c: &mut Criterion) {
+    let config = GameConfig::new(NUM_COLS, NUM_ROWS);
+    let mut group = c.benchmark_group(format!("army step on a {}x{} board", NUM_COLS, NUM_ROWS));
+
+    // Edge check, step and bottom check, the way `update` used to do them
+    group.bench_function("linear scan", |b| {
+        let mut army = linear_army(&Invaders::new(&config));
+        let mut direction = 1;
+        b.iter(|| {
+            let at_edge = if direction == -1 {
+                army.iter().map(|(x, _, _)| *x).min().unwrap_or(0) == 0
+            } else {
+                army.iter().map(|(x, _, _)| *x).max().unwrap_or(0) == config.num_cols - 1
+            };
+
+            if at_edge {
+                direction = -direction;
+                for invader in army.iter_mut() {
+                    invader.1 += 1;
+                }
+            } else {
+                for invader in army.iter_mut() {
+                    invader.0 = (invader.0 as i32 + direction) as usize;
+                }
+            }
+            black_box(army.iter().map(|(_, y, _)| *y).max().unwrap_or(0) >= config.num_rows - 1)
+        })
+    });
+
+    group.bench_function("occupancy grid", |b| {
+        let mut invaders = Invaders::new(&config);
+        b.iter(|| {
+            invaders.update(Duration::from_millis(2000));
+            black_box(invaders.reached_bottom())
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, hit_test_benchmark, move_benchmark);
+criterion_main!(benches);
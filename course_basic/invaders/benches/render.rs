@@ -0,0 +1,123 @@
+use std::{io::Write, time::Duration};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use crossterm::{cursor::MoveTo, style::{Attribute, Print, SetAttribute, SetAttributes, SetBackgroundColor, SetForegroundColor}, QueueableCommand};
+use invaders::{autopilot::Autopilot, config::GameConfig, frame::{new_frame, new_hud, stack, Cell, Drawable, Frame}, game::Game, render::{CrosstermRenderer, Renderer}};
+
+const NUM_FRAMES: usize = 600; // Ten seconds at 60fps
+const TICKS_PER_FRAME: usize = 2;
+const TICK: Duration = Duration::from_nanos(1_000_000_000 / 120);
+
+// Frames of a game played by the autopilot
+fn frames(config: &GameConfig) -> Vec<Frame> {
+    let mut game = Game::new(config, 7);
+    let mut autopilot = Autopilot::new();
+    let mut frames = Vec::with_capacity(NUM_FRAMES);
+
+    while frames.len() < NUM_FRAMES && !game.is_over() {
+        for _ in 0..TICKS_PER_FRAME {
+            if let Some(action) = autopilot.next_action(TICK, &game.players[0], &game.invaders) {
+                match action {
+                    invaders::bindings::Action::MoveLeft => game.players[0].move_left(),
+                    invaders::bindings::Action::MoveRight => game.players[0].move_right(),
+                    invaders::bindings::Action::Fire => {
+                        game.players[0].shoot();
+                    }
+                    _ => {}
+                }
+            }
+            game.update(TICK);
+        }
+
+        let mut hud = new_hud(config);
+        game.hud(0).draw(&mut hud);
+        let mut board = new_frame(config);
+        game.draw(&mut board);
+        frames.push(stack(&hud, &board));
+    }
+
+    frames
+}
+
+// What `CrosstermRenderer` used to do: column by column, a cursor move and a print for every changed cell.
+// Like now, only the parts of the style that changed are sent.
+fn per_cell_render<W: Write>(out: &mut W, last_frame: &Frame, curr_frame: &Frame, style: &mut Option<Cell>) {
+    for (x, col) in curr_frame.iter().enumerate() {
+        for (y, cell) in col.iter().enumerate() {
+            if *cell != last_frame[x][y] {
+                out.queue(MoveTo(x as u16, y as u16)).unwrap();
+                if style.map(|style| style.attrs) != Some(cell.attrs) {
+                    out.queue(SetAttribute(Attribute::Reset)).unwrap();
+                    out.queue(SetAttributes(cell.attrs)).unwrap();
+                    out.queue(SetForegroundColor(cell.fg)).unwrap();
+                    out.queue(SetBackgroundColor(cell.bg)).unwrap();
+                } else {
+                    if style.map(|style| style.fg) != Some(cell.fg) {
+                        out.queue(SetForegroundColor(cell.fg)).unwrap();
+                    }
+                    if style.map(|style| style.bg) != Some(cell.bg) {
+                        out.queue(SetBackgroundColor(cell.bg)).unwrap();
+                    }
+                }
+                *style = Some(*cell);
+                out.queue(Print(cell.glyph)).unwrap();
+            }
+        }
+    }
+    out.flush().unwrap();
+}
+
+fn per_cell_bytes(frames: &[Frame]) -> usize {
+    let mut out = Vec::new();
+    let mut style = None;
+    for pair in frames.windows(2) {
+        per_cell_render(&mut out, &pair[0], &pair[1], &mut style);
+    }
+    out.len()
+}
+
+fn batched_bytes(frames: &[Frame]) -> usize {
+    let mut renderer = CrosstermRenderer::new(Vec::new());
+    for pair in frames.windows(2) {
+        renderer.render(&pair[0], &pair[1], false);
+    }
+    renderer.get_mut().len()
+}
+
+// Every cell of one frame, the way it's drawn after a resize
+fn redraw_bytes(frame: &Frame) -> (usize, usize) {
+    let nothing = vec![vec![Cell::new(""); frame[0].len()]; frame.len()]; // Differs from every cell
+    let mut per_cell = Vec::new();
+    per_cell_render(&mut per_cell, &nothing, frame, &mut None);
+
+    let mut renderer = CrosstermRenderer::new(Vec::new());
+    renderer.render(frame, frame, true);
+
+    (per_cell.len(), renderer.get_mut().len())
+}
+
+pub fn render_benchmark(c: &mut Criterion) {
+    let config = GameConfig::default();
+    let frames = frames(&config);
+    let num_diffs = frames.len() - 1;
+
+    let per_cell = per_cell_bytes(&frames);
+    let batched = batched_bytes(&frames);
+    println!("per cell: {} bytes per frame", per_cell / num_diffs);
+    println!("batched:  {} bytes per frame", batched / num_diffs);
+    let (per_cell_redraw, batched_redraw) = redraw_bytes(&frames[num_diffs]);
+    println!("redraw:   {} bytes per cell, {} bytes batched", per_cell_redraw, batched_redraw); // Batched includes clearing the screen
+
+    let mut group = c.benchmark_group(format!("{} frames of a {}x{} game", num_diffs, config.num_cols, config.num_rows));
+
+    group.throughput(Throughput::Bytes(per_cell as u64));
+    group.bench_function("per cell", |b| b.iter(|| per_cell_bytes(&frames)));
+
+    group.throughput(Throughput::Bytes(batched as u64));
+    group.bench_function("batched", |b| b.iter(|| batched_bytes(&frames)));
+
+    group.finish();
+}
+
+criterion_group!(benches, render_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,96 @@
+use rusty_audio::Audio;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sound {
+    Explode,
+    Lose,
+    Move,
+    Pew,
+    Startup,
+    Ufo,
+    Win,
+}
+
+impl Sound {
+    pub const ALL: [Sound; 7] = [Sound::Explode, Sound::Lose, Sound::Move, Sound::Pew, Sound::Startup, Sound::Ufo, Sound::Win];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Sound::Explode => "explode",
+            Sound::Lose => "lose",
+            Sound::Move => "move",
+            Sound::Pew => "pew",
+            Sound::Startup => "startup",
+            Sound::Ufo => "ufo",
+            Sound::Win => "win",
+        }
+    }
+}
+
+pub trait SoundSink {
+    fn play(&mut self, sound: Sound);
+
+    // Blocks until every sound has finished playing
+    fn wait(&mut self) {}
+}
+
+// Plays the sounds on the default output device
+pub struct RustyAudioSink {
+    audio: Audio,
+}
+
+impl RustyAudioSink {
+    pub fn new() -> Self {
+        let mut audio = Audio::new();
+        for sound in Sound::ALL {
+            audio.add(sound.name(), format!("sounds/{}.wav", sound.name()));
+        }
+
+        Self { audio }
+    }
+}
+
+impl Default for RustyAudioSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SoundSink for RustyAudioSink {
+    fn play(&mut self, sound: Sound) {
+        self.audio.play(sound.name());
+    }
+
+    fn wait(&mut self) {
+        self.audio.wait();
+    }
+}
+
+// For `--mute` and machines without a sound device
+pub struct SilentSink;
+
+impl SoundSink for SilentSink {
+    fn play(&mut self, _sound: Sound) {}
+}
+
+// Keeps track of what would have been played, in order
+#[derive(Default)]
+pub struct RecordingSink {
+    played: Vec<Sound>,
+}
+
+impl RecordingSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn played(&self) -> &[Sound] {
+        &self.played
+    }
+}
+
+impl SoundSink for RecordingSink {
+    fn play(&mut self, sound: Sound) {
+        self.played.push(sound);
+    }
+}
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use rusty_time::timer::Timer;
+
+use crate::{bindings::Action, invaders::Invaders, player::Player};
+
+// How many rows above the player an enemy shot is worth dodging
+const DANGER_ROWS: usize = 4;
+
+// Plays in place of a human: dodges the enemy shots, otherwise lines up under the closest invader and fires.
+// It only decides with what it reads from the board, so the same game always plays out the same way.
+pub struct Autopilot {
+    think_timer: Timer, // One action at a time, at about the speed of a human
+}
+
+impl Autopilot {
+    pub fn new() -> Self {
+        Self {
+            think_timer: Timer::from_millis(80),
+        }
+    }
+
+    // The action to take this tick, if any
+    pub fn next_action(&mut self, delta: Duration, player: &Player, invaders: &Invaders) -> Option<Action> {
+        self.think_timer.update(delta);
+        if !self.think_timer.ready || player.is_dead() {
+            return None;
+        }
+        self.think_timer.reset();
+
+        let x = player.x();
+        if !player.is_invulnerable() && Self::in_danger(x, player.y(), invaders) {
+            // Step aside to whichever neighbour column is safe
+            let left_safe = x > 0 && !Self::in_danger(x - 1, player.y(), invaders);
+            return Some(if left_safe { Action::MoveLeft } else { Action::MoveRight });
+        }
+
+        // Closest column with an invader in it, the lowest invader of a column is the one a shot hits
+        let target = invaders.iter().map(|invader| invader.x()).min_by_key(|target_x| target_x.abs_diff(x))?;
+
+        if target < x && !Self::in_danger(x - 1, player.y(), invaders) {
+            Some(Action::MoveLeft)
+        } else if target > x && !Self::in_danger(x + 1, player.y(), invaders) {
+            Some(Action::MoveRight)
+        } else if target == x {
+            Some(Action::Fire)
+        } else {
+            None
+        }
+    }
+
+    fn in_danger(x: usize, y: usize, invaders: &Invaders) -> bool {
+        invaders
+            .shots()
+            .iter()
+            .any(|shot| !shot.exploding && shot.x == x && shot.y <= y && y - shot.y <= DANGER_ROWS)
+    }
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
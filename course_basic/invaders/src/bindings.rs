@@ -0,0 +1,189 @@
+use std::{collections::HashMap, fs, io::{self, ErrorKind}, path::{Path, PathBuf}};
+
+use crossterm::event::KeyCode;
+use serde::Deserialize;
+
+use crate::invalid_data;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Fire,
+    SwitchWeapon,
+    Pause,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 6] = [Action::MoveLeft, Action::MoveRight, Action::Fire, Action::SwitchWeapon, Action::Pause, Action::Quit];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "MoveLeft",
+            Action::MoveRight => "MoveRight",
+            Action::Fire => "Fire",
+            Action::SwitchWeapon => "SwitchWeapon",
+            Action::Pause => "Pause",
+            Action::Quit => "Quit",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().find(|action| action.name() == name).copied()
+    }
+
+    fn default_keys(&self) -> Vec<KeyCode> {
+        match self {
+            Action::MoveLeft => vec![KeyCode::Left],
+            Action::MoveRight => vec![KeyCode::Right],
+            Action::Fire => vec![KeyCode::Char(' ')],
+            Action::SwitchWeapon => vec![KeyCode::Tab, KeyCode::Char('w')],
+            Action::Pause => vec![KeyCode::Char('p')],
+            Action::Quit => vec![KeyCode::Esc, KeyCode::Char('q')],
+        }
+    }
+}
+
+// Layout of the config file, every action is optional and keeps its default keys when left out.
+// A key belongs to a single action, a key from the file takes precedence over the defaults:
+//
+// move_left = ["Left", "h"]
+// move_right = ["Right", "l"]
+// fire = ["Space", "k"]
+// switch_weapon = ["Tab"]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BindingsFile {
+    move_left: Option<Vec<String>>,
+    move_right: Option<Vec<String>>,
+    fire: Option<Vec<String>>,
+    switch_weapon: Option<Vec<String>>,
+    pause: Option<Vec<String>>,
+    quit: Option<Vec<String>>,
+}
+
+#[derive(Clone)]
+pub struct KeyBindings {
+    actions: HashMap<KeyCode, Action>,
+    keys: HashMap<Action, Vec<KeyCode>>,
+}
+
+impl KeyBindings {
+    // `keys.toml` in the user's config directory, e.g. ~/.config/invaders on Linux
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("invaders").join("keys.toml"))
+    }
+
+    // A missing file means the default controls
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+
+        let file: BindingsFile = toml::from_str(&contents)
+            .map_err(|e| invalid_data(format!("keys.toml: {}", e)))?;
+
+        // Keys from the file come first, each of them for a single action
+        let mut keys = HashMap::new();
+        let mut taken: HashMap<KeyCode, Action> = HashMap::new();
+        for action in Action::ALL {
+            let names = match action {
+                Action::MoveLeft => &file.move_left,
+                Action::MoveRight => &file.move_right,
+                Action::Fire => &file.fire,
+                Action::SwitchWeapon => &file.switch_weapon,
+                Action::Pause => &file.pause,
+                Action::Quit => &file.quit,
+            };
+            let Some(names) = names else { continue };
+
+            let mut action_keys = Vec::new();
+            for name in names {
+                let key = parse_key(name).ok_or_else(|| invalid_data(format!("keys.toml: unknown key '{}'", name)))?;
+                match taken.insert(key, action) {
+                    Some(other) if other != action => {
+                        return Err(invalid_data(format!("keys.toml: '{}' is bound to both {} and {}", name, other.name(), action.name())));
+                    }
+                    _ => action_keys.push(key),
+                }
+            }
+            keys.insert(action, action_keys);
+        }
+
+        // The actions left out keep their default keys, except the ones the file gave to another action
+        for action in Action::ALL {
+            keys.entry(action).or_insert_with(|| action.default_keys().into_iter().filter(|key| !taken.contains_key(key)).collect());
+        }
+
+        Ok(Self::from_keys(keys))
+    }
+
+    fn from_keys(keys: HashMap<Action, Vec<KeyCode>>) -> Self {
+        let mut actions = HashMap::new();
+        for action in Action::ALL {
+            for key in keys[&action].iter() {
+                actions.insert(*key, action);
+            }
+        }
+
+        Self { actions, keys }
+    }
+
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        self.actions.get(&key).copied()
+    }
+
+    // Name of the first key bound to the action, for the menus
+    pub fn label(&self, action: Action) -> String {
+        self.keys[&action].first().map(|key| key_label(*key)).unwrap_or_else(|| "-".to_string())
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::from_keys(Action::ALL.iter().map(|action| (*action, action.default_keys())).collect())
+    }
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    let key = match name.to_ascii_lowercase().as_str() {
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "space" => KeyCode::Char(' '),
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        _ => {
+            // Any other single character, as typed
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => return None,
+            }
+        }
+    };
+
+    Some(key)
+}
+
+fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::Left => "LEFT".to_string(),
+        KeyCode::Right => "RIGHT".to_string(),
+        KeyCode::Up => "UP".to_string(),
+        KeyCode::Down => "DOWN".to_string(),
+        KeyCode::Char(' ') => "SPACE".to_string(),
+        KeyCode::Enter => "ENTER".to_string(),
+        KeyCode::Esc => "ESC".to_string(),
+        KeyCode::Tab => "TAB".to_string(),
+        KeyCode::Backspace => "BKSP".to_string(),
+        KeyCode::Char(c) => c.to_ascii_uppercase().to_string(),
+        _ => "?".to_string(),
+    }
+}
@@ -0,0 +1,71 @@
+use crossterm::style::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::GameConfig, frame::{Cell, Drawable}};
+
+const BUNKER_WIDTH: usize = 4;
+const BUNKER_HEIGHT: usize = 2;
+const CELL_HEALTH: u8 = 3;
+
+#[derive(Serialize, Deserialize)]
+pub struct BunkerCell {
+    x: usize,
+    y: usize,
+    health: u8,
+}
+
+// Shields between the player row and the army, every shot takes a bite out of them
+#[derive(Serialize, Deserialize)]
+pub struct Bunkers {
+    cells: Vec<BunkerCell>,
+}
+
+impl Bunkers {
+    pub fn new(config: &GameConfig) -> Self {
+        let count = config.num_cols / 10;
+        let top = config.num_rows - 2 - BUNKER_HEIGHT; // Leave a free row above the player
+        let mut cells = Vec::new();
+
+        for i in 0..count {
+            // Evenly spread, each bunker centered in its own slice of the board
+            let center = config.num_cols * (2 * i + 1) / (2 * count);
+            let left = center - BUNKER_WIDTH / 2;
+
+            for x in left..left + BUNKER_WIDTH {
+                for y in top..top + BUNKER_HEIGHT {
+                    cells.push(BunkerCell { x, y, health: CELL_HEALTH });
+                }
+            }
+        }
+
+        Self { cells }
+    }
+
+    // Erodes the cell at the given position, returns true if there was one to absorb the shot
+    pub fn hit_at(&mut self, x: usize, y: usize) -> bool {
+        if let Some(idx) = self
+            .cells
+            .iter()
+            .position(|cell| cell.x == x && cell.y == y) {
+                self.cells[idx].health -= 1;
+                if self.cells[idx].health == 0 {
+                    self.cells.remove(idx);
+                }
+                return true
+        }
+
+        false
+    }
+}
+
+impl Drawable for Bunkers {
+    fn draw(&self, frame: &mut crate::frame::Frame) {
+        for cell in self.cells.iter() {
+            frame[cell.x][cell.y] = match cell.health {
+                3 => Cell::new("#").fg(Color::Green),
+                2 => Cell::new("=").fg(Color::DarkGreen),
+                _ => Cell::new("-").fg(Color::DarkYellow),
+            };
+        }
+    }
+}
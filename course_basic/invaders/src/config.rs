@@ -0,0 +1,85 @@
+use std::io;
+
+use crossterm::terminal;
+use serde::{Deserialize, Serialize};
+
+use crate::{frame::{BORDER, HUD_ROWS}, invalid_data};
+
+pub const DEFAULT_NUM_ROWS: usize = 20;
+pub const DEFAULT_NUM_COLS: usize = 40;
+
+// Below this the invader formation and the player don't fit on the board
+pub const MIN_NUM_ROWS: usize = 12;
+pub const MIN_NUM_COLS: usize = 20;
+
+// Above this every frame takes more memory than it's worth, and no terminal shows it anyway
+pub const MAX_NUM_ROWS: usize = 500;
+pub const MAX_NUM_COLS: usize = 500;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameConfig {
+    pub num_rows: usize,
+    pub num_cols: usize,
+}
+
+impl GameConfig {
+    pub fn new(num_cols: usize, num_rows: usize) -> Self {
+        Self {
+            num_rows: num_rows.clamp(MIN_NUM_ROWS, MAX_NUM_ROWS),
+            num_cols: num_cols.clamp(MIN_NUM_COLS, MAX_NUM_COLS),
+        }
+    }
+
+    // Board as big as the current terminal, minus the HUD and the border around them
+    pub fn from_terminal() -> crossterm::Result<Self> {
+        let (cols, rows) = terminal::size()?;
+        Ok(Self::new((cols as usize).saturating_sub(2 * BORDER), (rows as usize).saturating_sub(HUD_ROWS + 2 * BORDER)))
+    }
+
+    // Parses sizes written as `COLSxROWS`, e.g. `80x24`
+    pub fn parse_size(size: &str) -> io::Result<Self> {
+        let parsed = size
+            .split_once('x')
+            .and_then(|(cols, rows)| Some((cols.trim().parse::<usize>().ok()?, rows.trim().parse::<usize>().ok()?)));
+        let Some((num_cols, num_rows)) = parsed else {
+            return Err(invalid_data(format!("invalid size '{}', expected COLSxROWS", size)));
+        };
+        if num_cols > MAX_NUM_COLS || num_rows > MAX_NUM_ROWS {
+            return Err(invalid_data(format!("a {}x{} board is larger than {}x{}", num_cols, num_rows, MAX_NUM_COLS, MAX_NUM_ROWS)));
+        }
+
+        Ok(Self::new(num_cols, num_rows))
+    }
+
+    // Invaders start in the rows above this one
+    pub fn army_bottom(&self) -> usize {
+        self.num_rows / 2 - 1
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_NUM_COLS, DEFAULT_NUM_ROWS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(GameConfig::parse_size("80x24").unwrap(), GameConfig { num_cols: 80, num_rows: 24 });
+        assert_eq!(GameConfig::parse_size("1x1").unwrap(), GameConfig { num_cols: MIN_NUM_COLS, num_rows: MIN_NUM_ROWS });
+        assert!(GameConfig::parse_size("80by24").is_err());
+    }
+
+    #[test]
+    fn refuses_boards_too_large_to_allocate() {
+        let err = GameConfig::parse_size("100000x100000").unwrap_err();
+
+        assert_eq!(err.to_string(), "a 100000x100000 board is larger than 500x500");
+        assert!(GameConfig::parse_size("500x501").is_err());
+        assert!(GameConfig::parse_size("500x500").is_ok());
+    }
+}
@@ -1,22 +1,126 @@
-use crate::{NUM_COLS, NUM_ROWS};
+use crossterm::style::{Attribute, Attributes, Color};
 
-pub type Frame = Vec<Vec<&'static str>>;
+use crate::config::GameConfig;
 
-pub fn new_frame() -> Frame {
-    let mut cols: Vec<Vec<&str>> = Vec::with_capacity(NUM_COLS);
+// One character on screen, with its colors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cell {
+    pub glyph: &'static str,
+    pub fg: Color,
+    pub bg: Color,
+    pub attrs: Attributes,
+}
 
-    for _ in 0..NUM_COLS {
-        let mut col: Vec<&str> = Vec::with_capacity(NUM_ROWS);
-        
-        for _ in 0..NUM_ROWS {
-            col.push(" ");
+impl Cell {
+    pub fn new(glyph: &'static str) -> Self {
+        Self {
+            glyph,
+            fg: Color::White,
+            bg: Color::Black,
+            attrs: Attributes::default(),
         }
+    }
+
+    pub fn fg(self, fg: Color) -> Self {
+        Self { fg, ..self }
+    }
+
+    pub fn bg(self, bg: Color) -> Self {
+        Self { bg, ..self }
+    }
+
+    pub fn attr(mut self, attr: Attribute) -> Self {
+        self.attrs.set(attr);
+        self
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self::new(" ")
+    }
+}
+
+pub type Frame = Vec<Vec<Cell>>;
+
+// Lines of status text above the board
+pub const HUD_ROWS: usize = 1;
+
+// Width of the line drawn around the HUD and the board on a terminal
+pub const BORDER: usize = 1;
+
+// Printable ASCII, sliced to get a `&'static str` for every character of a text
+const PRINTABLE: &str = " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+// The few glyphs drawn outside of printable ASCII
+const SYMBOLS: [&str; 1] = ["♥"];
+
+pub fn new_frame(config: &GameConfig) -> Frame {
+    let mut cols: Vec<Vec<Cell>> = Vec::with_capacity(config.num_cols);
+
+    for _ in 0..config.num_cols {
+        let col: Vec<Cell> = vec![Cell::default(); config.num_rows];
         cols.push(col);
     }
 
     cols
 }
 
+pub fn new_hud(config: &GameConfig) -> Frame {
+    vec![vec![Cell::default(); HUD_ROWS]; config.num_cols]
+}
+
+// The whole screen, `top` is drawn above `bottom`
+pub fn stack(top: &Frame, bottom: &Frame) -> Frame {
+    top.iter()
+        .zip(bottom.iter())
+        .map(|(top_col, bottom_col)| top_col.iter().chain(bottom_col.iter()).copied().collect())
+        .collect()
+}
+
+// Blanks every cell, so the frame can be drawn on again instead of allocating a new one
+pub fn clear_frame(frame: &mut Frame) {
+    for col in frame.iter_mut() {
+        col.fill(Cell::default());
+    }
+}
+
+// Like `stack`, into a frame reused from one render to the next
+pub fn stack_into(top: &Frame, bottom: &Frame, screen: &mut Frame) {
+    screen.resize_with(top.len(), Vec::new);
+    for ((screen_col, top_col), bottom_col) in screen.iter_mut().zip(top.iter()).zip(bottom.iter()) {
+        screen_col.clear();
+        screen_col.extend_from_slice(top_col);
+        screen_col.extend_from_slice(bottom_col);
+    }
+}
+
+// Writes `text` from (x, y) to the right, clipped at the frame border
+pub fn draw_text(frame: &mut Frame, x: usize, y: usize, text: &str, fg: Color) {
+    for (i, c) in text.chars().enumerate() {
+        let Some(col) = frame.get_mut(x + i) else { break };
+        let Some(cell) = col.get_mut(y) else { break };
+
+        *cell = Cell::new(glyph(c)).fg(fg);
+    }
+}
+
+// The `&'static str` a cell needs to show `c`, `?` for characters we can't draw
+pub fn glyph(c: char) -> &'static str {
+    match (c as usize).checked_sub(' ' as usize) {
+        Some(idx) if idx < PRINTABLE.len() => &PRINTABLE[idx..idx + 1],
+        _ => SYMBOLS
+            .iter()
+            .find(|symbol| symbol.starts_with(c))
+            .copied()
+            .unwrap_or("?"),
+    }
+}
+
+pub fn draw_text_centered(frame: &mut Frame, y: usize, text: &str, fg: Color) {
+    let x = frame.len().saturating_sub(text.chars().count()) / 2;
+    draw_text(frame, x, y, text, fg);
+}
+
 pub trait Drawable {
     fn draw(&self, frame: &mut Frame);
 }
\ No newline at end of file
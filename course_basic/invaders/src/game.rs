@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{audio::Sound, bunkers::Bunkers, config::GameConfig, frame::{Drawable, Frame}, hud::Hud, invaders::Invaders, level::Level, mystery_ship::MysteryShip, player::Player, power_ups::PowerUps};
+
+// One game, from the first wave until the players lose
+#[derive(Serialize, Deserialize)]
+pub struct Game {
+    pub players: Vec<Player>, // The local player comes first
+    pub invaders: Invaders,
+    pub bunkers: Bunkers,
+    pub mystery_ship: MysteryShip,
+    pub power_ups: PowerUps,
+    pub wave: usize,
+    level: Option<Level>, // The formation of every wave, the classic one when None
+    rng: ChaCha12Rng, // Every random choice comes from here, so a game is reproducible from its seed. Same generator as `StdRng`, but it can be saved
+    config: GameConfig,
+}
+
+impl Game {
+    pub fn new(config: &GameConfig, seed: u64) -> Self {
+        Self::with_players(config, seed, 1)
+    }
+
+    // Co-op game, all players against the same army
+    pub fn with_players(config: &GameConfig, seed: u64, num_players: usize) -> Self {
+        Self {
+            players: (0..num_players).map(|slot| Player::for_slot(config, slot, num_players)).collect(),
+            invaders: Invaders::new(config),
+            bunkers: Bunkers::new(config),
+            mystery_ship: MysteryShip::new(config),
+            power_ups: PowerUps::new(config),
+            wave: 1,
+            level: None,
+            rng: ChaCha12Rng::seed_from_u64(seed),
+            config: *config,
+        }
+    }
+
+    // Every wave starts from the level's formation
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.invaders = Invaders::from_level(&self.config, &level, self.wave);
+        self.level = Some(level);
+        self
+    }
+
+    // Advances everything by `delta`, returns the sounds to play
+    pub fn update(&mut self, delta: Duration) -> Vec<Sound> {
+        let mut sounds = Vec::new();
+
+        for player in self.players.iter_mut() {
+            player.update(delta);
+        }
+
+        if self.invaders.update(delta) {
+            sounds.push(Sound::Move);
+        }
+
+        if self.invaders.fire(&mut self.rng) {
+            sounds.push(Sound::Pew);
+        }
+
+        if self.mystery_ship.update(delta, &mut self.rng) {
+            sounds.push(Sound::Ufo);
+        }
+
+        self.power_ups.update(delta);
+
+        for player in self.players.iter_mut() {
+            if player.detect_hits(&mut self.invaders, &mut self.bunkers, &mut self.mystery_ship, &mut self.power_ups, &mut self.rng) > 0 {
+                sounds.push(Sound::Explode);
+            }
+
+            // A dead player's spot doesn't catch anything, the power-up keeps falling for the others
+            if !player.is_dead() {
+                if let Some(kind) = self.power_ups.catch_at(player.x(), player.y()) {
+                    player.power_up(kind);
+                }
+            }
+        }
+
+        self.invaders.detect_bunker_hits(&mut self.bunkers);
+
+        for player in self.players.iter_mut() {
+            if player.detect_enemy_hits(&mut self.invaders) {
+                sounds.push(Sound::Explode);
+            }
+        }
+
+        // Next wave
+        if self.invaders.all_killed() {
+            sounds.push(Sound::Win);
+            self.wave += 1;
+            self.invaders = match &self.level {
+                Some(level) => Invaders::from_level(&self.config, level, self.wave),
+                None => Invaders::for_wave(&self.config, self.wave),
+            };
+        }
+
+        sounds
+    }
+
+    pub fn is_over(&mut self) -> bool {
+        self.invaders.reached_bottom() || self.players.iter().all(|player| player.is_dead())
+    }
+
+    // Co-op players share the score and the lives
+    pub fn score(&self) -> u32 {
+        self.players.iter().map(|player| player.score()).sum()
+    }
+
+    // The board the game is played on
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn level(&self) -> Option<&Level> {
+        self.level.as_ref()
+    }
+
+    pub fn hud(&self, high_score: u32) -> Hud {
+        Hud {
+            score: self.score(),
+            high_score,
+            wave: self.wave,
+            weapon: self.players[0].weapon().name(),
+            lives: self.players.iter().map(|player| player.lives()).sum(),
+            // The local player's, they are the one looking at the HUD
+            effects: self.players[0].effects().iter().map(|effect| (effect.kind, effect.timer.time_left)).collect(),
+        }
+    }
+}
+
+impl Drawable for Game {
+    fn draw(&self, frame: &mut Frame) {
+        self.bunkers.draw(frame);
+        self.power_ups.draw(frame);
+        for player in self.players.iter() {
+            player.draw(frame);
+        }
+
+        let drawables: [&dyn Drawable; 2] = [&self.invaders, &self.mystery_ship];
+        for drawable in drawables {
+            drawable.draw(frame);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use std::{fs, io::{self, ErrorKind}, path::{Path, PathBuf}};
+
+use crossterm::style::Color;
+
+use crate::{frame::{draw_text_centered, Drawable, Frame}, invalid_data};
+
+pub const MAX_ENTRIES: usize = 10;
+pub const INITIALS_LEN: usize = 3;
+
+#[derive(Clone)]
+pub struct HighScore {
+    pub initials: String,
+    pub score: u32,
+}
+
+// Top scores, best first, stored one `INITIALS SCORE` pair per line
+#[derive(Clone, Default)]
+pub struct HighScores {
+    entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    // `highscores.txt` in the user's data directory, e.g. ~/.local/share/invaders on Linux
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("invaders").join("highscores.txt"))
+    }
+
+    // A missing file is just an empty table
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+
+        Self::parse(&contents)
+    }
+
+    // The contents of a high scores file
+    pub fn parse(contents: &str) -> io::Result<Self> {
+        let mut high_scores = Self::default();
+        for (idx, line) in contents.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let entry = line
+                .split_once(' ')
+                .and_then(|(initials, score)| Some(HighScore { initials: initials.to_string(), score: score.trim().parse().ok()? }))
+                .ok_or_else(|| invalid_data(format!("high scores line {}: expected 'INITIALS SCORE'", idx + 1)))?;
+
+            high_scores.insert(&entry.initials, entry.score);
+        }
+
+        Ok(high_scores)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        if let Some(dir) = path.as_ref().parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(path, self.to_text())
+    }
+
+    // One `INITIALS SCORE` line per entry, as `parse` reads them
+    pub fn to_text(&self) -> String {
+        self.entries
+            .iter()
+            .map(|entry| format!("{} {}\n", entry.initials, entry.score))
+            .collect()
+    }
+
+    pub fn entries(&self) -> &[HighScore] {
+        &self.entries
+    }
+
+    pub fn best(&self) -> u32 {
+        self.entries.first().map(|entry| entry.score).unwrap_or(0)
+    }
+
+    // True if the score would make it into the table
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0 && (self.entries.len() < MAX_ENTRIES || self.entries.iter().any(|entry| score > entry.score))
+    }
+
+    pub fn insert(&mut self, initials: &str, score: u32) {
+        // After the entries with the same score, so older scores keep their place
+        let idx = self.entries.iter().position(|entry| score > entry.score).unwrap_or(self.entries.len());
+        self.entries.insert(idx, HighScore { initials: initials.to_string(), score });
+        self.entries.truncate(MAX_ENTRIES);
+    }
+}
+
+impl Drawable for HighScores {
+    fn draw(&self, frame: &mut Frame) {
+        draw_text_centered(frame, 0, "HIGH SCORES", Color::Yellow);
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            draw_text_centered(frame, i + 1, &format!("{:>2}. {:<3} {:>6}", i + 1, entry.initials, entry.score), Color::White);
+        }
+    }
+}
+
+// Screen shown after game over when the score made it into the table
+pub struct InitialsEntry {
+    score: u32,
+    initials: String,
+}
+
+impl InitialsEntry {
+    pub fn new(score: u32) -> Self {
+        Self {
+            score,
+            initials: String::new(),
+        }
+    }
+
+    // Only letters and digits, always upper case
+    pub fn push(&mut self, c: char) {
+        if c.is_ascii_alphanumeric() && self.initials.len() < INITIALS_LEN {
+            self.initials.push(c.to_ascii_uppercase());
+        }
+    }
+
+    pub fn pop(&mut self) {
+        self.initials.pop();
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.initials.len() == INITIALS_LEN
+    }
+
+    pub fn initials(&self) -> &str {
+        &self.initials
+    }
+}
+
+impl Drawable for InitialsEntry {
+    fn draw(&self, frame: &mut Frame) {
+        let middle = frame.first().map(|col| col.len()).unwrap_or(0) / 2;
+        let initials = format!("{:_<width$}", self.initials, width = INITIALS_LEN);
+
+        draw_text_centered(frame, middle - 3, "GAME OVER", Color::Red);
+        draw_text_centered(frame, middle - 1, &format!("SCORE {}", self.score), Color::White);
+        draw_text_centered(frame, middle, "NEW HIGH SCORE!", Color::Yellow);
+        draw_text_centered(frame, middle + 2, &format!("INITIALS: {}", initials), Color::Cyan);
+        draw_text_centered(frame, middle + 4, "ENTER TO SAVE", Color::White);
+    }
+}
@@ -0,0 +1,91 @@
+use std::time::{Duration, Instant};
+
+use crossterm::style::Color;
+
+use crate::{frame::{draw_text, Cell, Drawable, Frame, HUD_ROWS}, power_ups::PowerUpKind};
+
+// Status line drawn above the board
+pub struct Hud {
+    pub score: u32,
+    pub high_score: u32,
+    pub wave: usize,
+    pub weapon: &'static str,
+    pub lives: u32,
+    pub effects: Vec<(PowerUpKind, Duration)>, // Active power-ups and the time they have left
+}
+
+impl Drawable for Hud {
+    fn draw(&self, frame: &mut Frame) {
+        draw_text(frame, 0, 0, &format!("SCORE {} HI {} WAVE {} {}", self.score, self.high_score.max(self.score), self.wave, self.weapon), Color::White);
+
+        // Remaining lives in the right corner
+        let num_cols = frame.len();
+        for i in 0..(self.lives as usize).min(num_cols) {
+            frame[num_cols - 1 - i][0] = Cell::new("♥").fg(Color::Red);
+        }
+
+        // Power-ups left of the lives, each with the seconds it has left
+        let mut right = num_cols.saturating_sub(self.lives as usize + 1);
+        for (kind, time_left) in self.effects.iter().rev() {
+            let text = format!("{}{}", kind.glyph(), time_left.as_secs() + 1);
+            right = right.saturating_sub(text.len() + 1);
+            draw_text(frame, right, 0, &text, kind.color());
+        }
+    }
+}
+
+// Frames rendered and ticks simulated during the last second, for `--debug`
+pub struct PerfCounter {
+    since: Instant,
+    frames: u32,
+    ticks: u32,
+    fps: u32,
+    tps: u32,
+}
+
+impl PerfCounter {
+    pub fn new() -> Self {
+        Self {
+            since: Instant::now(),
+            frames: 0,
+            ticks: 0,
+            fps: 0,
+            tps: 0,
+        }
+    }
+
+    pub fn count_tick(&mut self) {
+        self.ticks += 1;
+        self.roll_over();
+    }
+
+    pub fn count_frame(&mut self) {
+        self.frames += 1;
+        self.roll_over();
+    }
+
+    fn roll_over(&mut self) {
+        if self.since.elapsed() >= Duration::from_secs(1) {
+            self.fps = self.frames;
+            self.tps = self.ticks;
+            self.frames = 0;
+            self.ticks = 0;
+            self.since = Instant::now();
+        }
+    }
+}
+
+impl Default for PerfCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drawable for PerfCounter {
+    // On the first board row, right below the lives
+    fn draw(&self, frame: &mut Frame) {
+        let text = format!("FPS {} TPS {}", self.fps, self.tps);
+        let x = frame.len().saturating_sub(text.len());
+        draw_text(frame, x, HUD_ROWS, &text, Color::DarkGrey);
+    }
+}
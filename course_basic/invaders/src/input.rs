@@ -0,0 +1,264 @@
+use std::{collections::VecDeque, io::{self, ErrorKind}, thread, time::{Duration, Instant}};
+
+use crossterm::{event::{self, Event, KeyCode}, terminal};
+
+use crate::{bindings::{Action, KeyBindings}, config::GameConfig, frame::HUD_ROWS, render, replay::Replay};
+
+// The simulation always moves forward in steps of this size, 120 per second
+pub const TICK: Duration = Duration::from_nanos(1_000_000_000 / 120);
+
+// After a stall, e.g. a suspended process, skip ahead instead of simulating every missed tick
+const MAX_CATCH_UP: Duration = Duration::from_millis(250);
+
+// How long a replay waits before looking at the terminal size again, while it's too small
+const RESIZE_WAIT: Duration = Duration::from_millis(16);
+
+// What a key press means to the game: one of the bound actions, or a key the menus need
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Input {
+    Action(Action),
+    Enter,
+    Backspace,
+    Text(char), // A printable key, e.g. the initials of a new high score
+}
+
+impl Input {
+    pub fn name(&self) -> String {
+        match self {
+            Input::Action(action) => action.name().to_string(),
+            Input::Enter => "Enter".to_string(),
+            Input::Backspace => "Backspace".to_string(),
+            Input::Text(c) => format!("Text:{}", *c as u32), // Code point, so a space doesn't break the replay format
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Input> {
+        let input = match name {
+            "Enter" => Input::Enter,
+            "Backspace" => Input::Backspace,
+            _ => match name.strip_prefix("Text:") {
+                Some(code) => Input::Text(std::char::from_u32(code.parse().ok()?)?),
+                None => Input::Action(Action::from_name(name)?),
+            },
+        };
+
+        Some(input)
+    }
+}
+
+// Everything the game loop consumes during one tick
+pub struct Tick {
+    pub delta: Duration,
+    pub inputs: Vec<Input>,
+}
+
+// Where the game loop gets the player's input from, and how much time passed while it was given
+pub trait InputSource {
+    // Ticks due since the last call, empty when none is due yet, None once the input ran out.
+    // While the game is `typing`, printable keys are text whatever they're bound to.
+    fn next_ticks(&mut self, typing: bool) -> crossterm::Result<Option<Vec<Tick>>>;
+}
+
+// Bound keys are their action, except for typing, when Esc is the only one left to back out of it
+fn to_input(key: KeyCode, bindings: &KeyBindings, typing: bool) -> Option<Input> {
+    match (key, bindings.action(key)) {
+        (KeyCode::Char(c), _) if typing => Some(Input::Text(c)),
+        (KeyCode::Enter, _) if typing => Some(Input::Enter),
+        (KeyCode::Backspace, _) if typing => Some(Input::Backspace),
+        (KeyCode::Esc, _) if typing => Some(Input::Action(Action::Quit)),
+        (_, Some(action)) => Some(Input::Action(action)),
+        (KeyCode::Enter, None) => Some(Input::Enter),
+        (KeyCode::Backspace, None) => Some(Input::Backspace),
+        (KeyCode::Char(c), None) => Some(Input::Text(c)),
+        _ => None,
+    }
+}
+
+fn fits(config: &GameConfig, size: (u16, u16)) -> bool {
+    render::fits_terminal(config.num_cols, config.num_rows + HUD_ROWS, size)
+}
+
+// Waits up to `timeout` for terminal events: keys are queued, resizes tell whether the game still fits
+fn poll_events(timeout: Duration, config: &GameConfig, keys: &mut Vec<KeyCode>, too_small: &mut bool) -> crossterm::Result<()> {
+    let mut timeout = timeout;
+    while event::poll(timeout)? {
+        match event::read()? {
+            Event::Key(key_event) => keys.push(key_event.code),
+            Event::Resize(cols, rows) => *too_small = !fits(config, (cols, rows)),
+            _ => {}
+        }
+        timeout = Duration::default();
+    }
+
+    Ok(())
+}
+
+// Someone playing on the terminal, in real time
+pub struct Keyboard {
+    config: GameConfig,
+    bindings: KeyBindings,
+    instant: Instant,
+    accumulator: Duration, // Time not simulated yet
+    pending_keys: Vec<KeyCode>,
+    too_small: bool, // Nothing moves while the terminal can't show the whole board
+}
+
+impl Keyboard {
+    pub fn new(config: &GameConfig, bindings: &KeyBindings) -> crossterm::Result<Self> {
+        Ok(Self {
+            config: *config,
+            bindings: bindings.clone(),
+            instant: Instant::now(),
+            accumulator: Duration::ZERO,
+            pending_keys: Vec::new(),
+            too_small: !fits(config, terminal::size()?),
+        })
+    }
+}
+
+impl InputSource for Keyboard {
+    fn next_ticks(&mut self, typing: bool) -> crossterm::Result<Option<Vec<Tick>>> {
+        // Sleep until a key is pressed or the next tick is due, instead of spinning
+        let timeout = TICK.saturating_sub(self.accumulator + self.instant.elapsed());
+        poll_events(timeout, &self.config, &mut self.pending_keys, &mut self.too_small)?;
+
+        self.accumulator = (self.accumulator + self.instant.elapsed()).min(MAX_CATCH_UP);
+        self.instant = Instant::now();
+
+        // The game waits, keys included, until the terminal is large enough again. Quitting still works, right away,
+        // as the player may not be able to resize it. The menus that would ask what to do can't be shown, the session
+        // leaves without them once the input ends.
+        if self.too_small {
+            if self.pending_keys.iter().any(|key| self.bindings.action(*key) == Some(Action::Quit)) {
+                return Ok(None);
+            }
+            self.accumulator = Duration::ZERO;
+            self.pending_keys.clear();
+        }
+
+        // Keys wait for the next tick, then go with the first one
+        let mut ticks = Vec::new();
+        while self.accumulator >= TICK {
+            self.accumulator -= TICK;
+            let inputs = self.pending_keys.drain(..).filter_map(|key| to_input(key, &self.bindings, typing)).collect();
+            ticks.push(Tick { delta: TICK, inputs });
+        }
+
+        Ok(Some(ticks))
+    }
+}
+
+// The inputs and deltas of a recording, so the same frames are produced again
+pub struct ReplayInput {
+    replay: Replay,
+    watched: bool, // Played back at the speed it was recorded on a terminal, instead of as fast as possible
+    too_small: bool,
+}
+
+impl ReplayInput {
+    pub fn new(replay: Replay, watched: bool) -> Self {
+        Self { replay, watched, too_small: false }
+    }
+}
+
+impl InputSource for ReplayInput {
+    fn next_ticks(&mut self, _typing: bool) -> crossterm::Result<Option<Vec<Tick>>> {
+        if !self.watched {
+            return Ok(self.replay.next_tick().map(|tick| vec![tick]));
+        }
+
+        // Only resizes count, the inputs come from the recording
+        poll_events(Duration::default(), self.replay.config(), &mut Vec::new(), &mut self.too_small)?;
+        if self.too_small {
+            thread::sleep(RESIZE_WAIT);
+            return Ok(Some(Vec::new()));
+        }
+
+        let tick = self.replay.next_tick();
+        if let Some(tick) = &tick {
+            thread::sleep(tick.delta);
+        }
+        Ok(tick.map(|tick| vec![tick]))
+    }
+}
+
+// Nobody at the keyboard: one tick after the other, without waiting for the clock
+pub struct Unattended;
+
+impl InputSource for Unattended {
+    fn next_ticks(&mut self, _typing: bool) -> crossterm::Result<Option<Vec<Tick>>> {
+        Ok(Some(vec![Tick { delta: TICK, inputs: Vec::new() }]))
+    }
+}
+
+// A fixed sequence of steps, e.g. to drive a game from a test:
+//
+// enter, move left 3, fire, wait 500ms
+//
+// Steps are separated by commas. An action (its name, case and spaces don't matter), `enter` or `backspace` is pressed
+// once, or as many times as the number after it, one tick apart. `type` is followed by text, typed one character
+// per tick. `wait` takes a duration in `ms` or `s`. No key bindings involved, the steps are what the game gets.
+pub struct ScriptedInput {
+    ticks: VecDeque<Tick>,
+}
+
+impl ScriptedInput {
+    pub fn parse(script: &str) -> io::Result<Self> {
+        let mut ticks = VecDeque::new();
+
+        for step in script.split(',').map(str::trim).filter(|step| !step.is_empty()) {
+            let invalid = |msg: &str| io::Error::new(ErrorKind::InvalidData, format!("step '{}': {}", step, msg));
+            let mut words: Vec<&str> = step.split_whitespace().collect();
+
+            if words[0].eq_ignore_ascii_case("wait") {
+                let duration = words.get(1).and_then(|duration| parse_duration(duration)).ok_or_else(|| invalid("expected a duration, e.g. 500ms"))?;
+                for _ in 0..duration.as_nanos().div_ceil(TICK.as_nanos()) {
+                    ticks.push_back(Tick { delta: TICK, inputs: Vec::new() });
+                }
+                continue;
+            }
+
+            if words[0].eq_ignore_ascii_case("type") {
+                for c in step[words[0].len()..].trim().chars() {
+                    ticks.push_back(Tick { delta: TICK, inputs: vec![Input::Text(c)] });
+                }
+                continue;
+            }
+
+            let times = match words.last().and_then(|times| times.parse::<usize>().ok()) {
+                Some(times) => {
+                    words.pop();
+                    times
+                }
+                None => 1,
+            };
+
+            let name = words.concat().to_lowercase();
+            let input = match Action::ALL.iter().find(|action| action.name().to_lowercase() == name) {
+                Some(action) => Input::Action(*action),
+                None if name == "enter" => Input::Enter,
+                None if name == "backspace" => Input::Backspace,
+                None => return Err(invalid("unknown action")),
+            };
+
+            for _ in 0..times {
+                ticks.push_back(Tick { delta: TICK, inputs: vec![input] });
+            }
+        }
+
+        Ok(Self { ticks })
+    }
+}
+
+fn parse_duration(duration: &str) -> Option<Duration> {
+    match duration.strip_suffix("ms") {
+        Some(millis) => millis.parse().ok().map(Duration::from_millis),
+        None => duration.strip_suffix('s')?.parse().ok().map(Duration::from_secs),
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn next_ticks(&mut self, _typing: bool) -> crossterm::Result<Option<Vec<Tick>>> {
+        Ok(self.ticks.pop_front().map(|tick| vec![tick]))
+    }
+}
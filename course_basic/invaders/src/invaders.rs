@@ -1,57 +1,250 @@
 use std::{time::Duration, cmp::max};
 
+use crossterm::style::{Attribute, Color};
+use rand::{Rng, seq::SliceRandom};
 use rusty_time::timer::Timer;
+use serde::{Deserialize, Serialize};
 
-use crate::{NUM_COLS, NUM_ROWS, frame::Drawable};
+use crate::{bunkers::Bunkers, config::GameConfig, frame::{Cell, Drawable}, level::Level, shot::Shot};
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InvaderKind {
+    Drone,
+    Crab,
+    Squid,
+    Commander,
+    Armored, // Takes two shots
+}
+
+impl InvaderKind {
+    pub const ALL: [InvaderKind; 5] = [InvaderKind::Drone, InvaderKind::Crab, InvaderKind::Squid, InvaderKind::Commander, InvaderKind::Armored];
+
+    // Classic formation, from the front row backwards
+    const ROWS: [InvaderKind; 4] = [InvaderKind::Drone, InvaderKind::Crab, InvaderKind::Squid, InvaderKind::Commander];
+
+    pub fn points(&self) -> u32 {
+        match self {
+            InvaderKind::Drone => 10,
+            InvaderKind::Crab => 20,
+            InvaderKind::Squid => 30,
+            InvaderKind::Commander => 40,
+            InvaderKind::Armored => 50,
+        }
+    }
+
+    pub fn hit_points(&self) -> u8 {
+        match self {
+            InvaderKind::Armored => 2,
+            _ => 1,
+        }
+    }
+
+    // One glyph per animation frame
+    pub fn glyphs(&self) -> [&'static str; 2] {
+        match self {
+            InvaderKind::Drone => ["x", "+"],
+            InvaderKind::Crab => ["v", "^"],
+            InvaderKind::Squid => ["o", "O"],
+            InvaderKind::Commander => ["w", "m"],
+            InvaderKind::Armored => ["H", "#"],
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            InvaderKind::Drone => Color::Green,
+            InvaderKind::Crab => Color::Yellow,
+            InvaderKind::Squid => Color::Cyan,
+            InvaderKind::Commander => Color::Magenta,
+            InvaderKind::Armored => Color::White,
+        }
+    }
+
+    // Character standing for the kind in level files
+    pub fn symbol(&self) -> char {
+        match self {
+            InvaderKind::Drone => '1',
+            InvaderKind::Crab => '2',
+            InvaderKind::Squid => '3',
+            InvaderKind::Commander => '4',
+            InvaderKind::Armored => 'H',
+        }
+    }
+
+    pub fn from_symbol(symbol: char) -> Option<InvaderKind> {
+        InvaderKind::ALL.iter().find(|kind| kind.symbol() == symbol).copied()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Invader {
     x: usize,
-    y: usize
+    y: usize,
+    kind: InvaderKind,
+    hp: u8,
+}
+
+impl Invader {
+    pub fn new(x: usize, y: usize, kind: InvaderKind) -> Self {
+        Self { x, y, kind, hp: kind.hit_points() }
+    }
+
+    pub fn x(&self) -> usize {
+        self.x
+    }
+
+    pub fn y(&self) -> usize {
+        self.y
+    }
+
+    pub fn kind(&self) -> InvaderKind {
+        self.kind
+    }
+
+    pub fn points(&self) -> u32 {
+        self.kind.points()
+    }
+
+    pub fn is_damaged(&self) -> bool {
+        self.hp < self.kind.hit_points()
+    }
+}
+
+// What a shot did to the invader it hit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hit {
+    Damaged,
+    Killed(u32), // With the points it was worth
+}
+
+// Damaged invaders flash for a moment, by formation cell
+#[derive(Serialize, Deserialize)]
+struct Flash {
+    x: usize,
+    y: usize,
+    #[serde(with = "crate::save::timer")]
+    timer: Timer,
 }
 
+// The army lives on an occupancy grid in formation coordinates, where it stood at the start of the wave.
+// Moving only changes the offset, and the per column and per row counts keep the edges of the formation
+// at hand, so hit tests, edge and bottom checks never scan the army.
+#[derive(Serialize, Deserialize)]
 pub struct Invaders {
-    pub army: Vec<Invader>,
+    grid: Vec<Vec<Option<Invader>>>, // [x][y], positions are the ones at the start of the wave
+    offset_x: i64,
+    offset_y: usize,
+    column_counts: Vec<usize>,
+    row_counts: Vec<usize>,
+    first_column: usize, // Edges of the formation, they only ever shrink
+    last_column: usize,
+    last_row: usize,
+    count: usize,
+    flashes: Vec<Flash>,
+    #[serde(with = "crate::save::timer")]
     move_timer: Timer,
     direction: i32,
+    shots: Vec<Shot>,
+    #[serde(with = "crate::save::timer")]
+    fire_timer: Timer,
+    config: GameConfig,
 }
 
 impl Invaders {
-    pub fn new() -> Self {
+    pub fn new(config: &GameConfig) -> Self {
+        Self::for_wave(config, 1)
+    }
+
+    // Every wave after the first starts one row lower and moves faster, from the third one the back row is armored
+    pub fn for_wave(config: &GameConfig, wave: usize) -> Self {
+        let offset = (wave - 1).min(config.num_rows / 4); // Keep clear of the bunkers
         let mut army = Vec::new();
-        for x in 0..NUM_COLS {
-            for y in 0..NUM_ROWS {
-                if (x > 1) && (x < NUM_COLS - 2) && (y > 0) && (y < 9) && (x % 2 == 0) && (y % 2 == 0) {
-                    army.push(Invader { x, y });
+        for x in 0..config.num_cols {
+            for y in 0..config.num_rows {
+                if (x > 1) && (x < config.num_cols - 2) && (y > 0) && (y < config.army_bottom()) && (x % 2 == 0) && (y % 2 == 0) {
+                    // The further back the row, the more it's worth: 10 points for the front row, +10 for each row behind
+                    let row = (config.army_bottom() - y).div_ceil(2) - 1;
+                    let kind = match InvaderKind::ROWS.get(row) {
+                        _ if wave >= 3 && y == 2 => InvaderKind::Armored,
+                        Some(kind) => *kind,
+                        None => InvaderKind::Commander,
+                    };
+                    army.push(Invader::new(x, y + offset, kind));
                 }
             }
         }
 
+        Self::with_army(config, army, wave)
+    }
+
+    // Formation read from a level file, waves still start lower and move faster
+    pub fn from_level(config: &GameConfig, level: &Level, wave: usize) -> Self {
+        let offset = (wave - 1).min(config.num_rows / 4);
+        let army = level
+            .invaders(config)
+            .map(|(x, y, kind)| Invader::new(x, y + offset, kind))
+            .collect();
+
+        Self::with_army(config, army, wave)
+    }
+
+    fn with_army(config: &GameConfig, army: Vec<Invader>, wave: usize) -> Self {
+        let move_millis = max(2000 - 250 * (wave as i64 - 1), 500);
+
+        let mut grid = vec![vec![None; config.num_rows]; config.num_cols];
+        let mut column_counts = vec![0; config.num_cols];
+        let mut row_counts = vec![0; config.num_rows];
+        for invader in army.iter() {
+            grid[invader.x][invader.y] = Some(*invader);
+            column_counts[invader.x] += 1;
+            row_counts[invader.y] += 1;
+        }
+
         Self {
-            army,
-            move_timer: Timer::from_millis(2000),
-            direction: 1
+            grid,
+            offset_x: 0,
+            offset_y: 0,
+            first_column: army.iter().map(|invader| invader.x).min().unwrap_or(0),
+            last_column: army.iter().map(|invader| invader.x).max().unwrap_or(0),
+            last_row: army.iter().map(|invader| invader.y).max().unwrap_or(0),
+            column_counts,
+            row_counts,
+            count: army.len(),
+            flashes: Vec::new(),
+            move_timer: Timer::from_millis(move_millis as u64),
+            direction: 1,
+            shots: Vec::new(),
+            fire_timer: Timer::from_millis(1000),
+            config: *config,
         }
     }
 
     pub fn update(&mut self, delta: Duration) -> bool{
         self.move_timer.update(delta);
+        self.fire_timer.update(delta);
+
+        for shot in self.shots.iter_mut() {
+            shot.update(delta);
+        }
+        self.shots.retain(|shot| !shot.dead());
+
+        for flash in self.flashes.iter_mut() {
+            flash.timer.update(delta);
+        }
+        self.flashes.retain(|flash| !flash.timer.ready);
 
         if self.move_timer.ready {
             self.move_timer.reset();
             let mut downwards = false;
 
             if self.direction == -1 {
-                let min_x = self.army.iter().map(|invader| invader.x).min().unwrap_or(0);
-
-                if min_x == 0 {
+                if self.count == 0 || self.board_x(self.first_column) == 0 {
                     // Change direction and go down one line
                     self.direction = 1;
                     downwards = true;
                 }
             } else {
-                let max_x = self.army.iter().map(|invader| invader.x).max().unwrap_or(0);
-
-                if max_x == NUM_COLS - 1 {
+                if self.count > 0 && self.board_x(self.last_column) == self.config.num_cols - 1 {
                     // Change direction and go down one line
                     self.direction = -1;
                     downwards = true;
@@ -62,13 +255,9 @@ impl Invaders {
                 let new_duration = max(self.move_timer.duration.as_millis() - 250, 250); // Increase movement speed
                 self.move_timer = Timer::from_millis(new_duration as u64);
 
-                for invader in self.army.iter_mut() {
-                    invader.y += 1;
-                }          
+                self.offset_y += 1;
             } else {
-                for invader in self.army.iter_mut() {
-                    invader.x = ((invader.x as i32) + self.direction) as usize;
-                }   
+                self.offset_x += self.direction as i64;
             }
             
             return true;
@@ -77,20 +266,120 @@ impl Invaders {
         false
     }
 
+    // A random invader of the front row drops a shot, at most 3 falling at the same time
+    pub fn fire<R: Rng>(&mut self, rng: &mut R) -> bool {
+        if !self.fire_timer.ready || self.shots.len() >= 3 {
+            return false;
+        }
+        self.fire_timer.reset();
+
+        // Only the lowest invader of each column has a free line of fire
+        let front_row: Vec<Invader> = (self.first_column..=self.last_column)
+            .filter(|x| self.column_counts[*x] > 0)
+            .filter_map(|x| {
+                let y = (0..=self.last_row).rev().find(|y| self.grid[x][*y].is_some())?;
+                self.grid[x][y].map(|invader| self.on_board(invader))
+            })
+            .filter(|invader| invader.y + 1 < self.config.num_rows)
+            .collect();
+
+        if let Some(shooter) = front_row.choose(rng) {
+            self.shots.push(Shot::new_downward(shooter.x, shooter.y + 1, self.config.num_rows - 1));
+            return true;
+        }
+
+        false
+    }
+
+    // Shots on their way down, exploding ones included
+    pub fn shots(&self) -> &[Shot] {
+        &self.shots
+    }
+
+    // Every invader still alive, with its position on the board
+    pub fn iter(&self) -> impl Iterator<Item = Invader> + '_ {
+        let columns = if self.count > 0 { self.first_column..self.last_column + 1 } else { 0..0 };
+
+        columns.flat_map(move |x| self.grid[x][..=self.last_row].iter().filter_map(|invader| invader.map(|invader| self.on_board(invader))))
+    }
+
     pub fn all_killed(&mut self) -> bool {
-        self.army.is_empty()
+        self.count == 0
     }
 
     pub fn reached_bottom(&mut self) -> bool {
-        self.army.iter().map(|invader| invader.y).max().unwrap_or(0) >= NUM_ROWS - 1
+        self.count > 0 && self.last_row + self.offset_y >= self.config.num_rows - 1
+    }
+
+    // None when there is no invader at the given cell
+    pub fn kill_invader_at(&mut self, x: usize, y: usize) -> Option<Hit> {
+        let (x, y) = self.formation_position(x, y)?;
+        let invader = self.grid[x][y].as_mut()?;
+
+        invader.hp -= 1;
+        if invader.hp > 0 {
+            self.flashes.retain(|flash| flash.x != x || flash.y != y);
+            self.flashes.push(Flash { x, y, timer: Timer::from_millis(300) });
+            return Some(Hit::Damaged);
+        }
+
+        let points = invader.points();
+        self.grid[x][y] = None;
+
+        self.count -= 1;
+        self.column_counts[x] -= 1;
+        self.row_counts[y] -= 1;
+
+        if self.count > 0 {
+            while self.column_counts[self.first_column] == 0 {
+                self.first_column += 1;
+            }
+            while self.column_counts[self.last_column] == 0 {
+                self.last_column -= 1;
+            }
+            while self.row_counts[self.last_row] == 0 {
+                self.last_row -= 1;
+            }
+        }
+
+        Some(Hit::Killed(points))
+    }
+
+    // Copy of a grid invader, at its position on the board
+    fn on_board(&self, invader: Invader) -> Invader {
+        Invader { x: self.board_x(invader.x), y: invader.y + self.offset_y, ..invader }
+    }
+
+    fn board_x(&self, x: usize) -> usize {
+        (x as i64 + self.offset_x) as usize
+    }
+
+    // Grid cell under a board position, None when it's outside of the formation
+    fn formation_position(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        let x = usize::try_from(x as i64 - self.offset_x).ok()?;
+        let y = y.checked_sub(self.offset_y)?;
+        (x < self.grid.len() && y < self.config.num_rows).then_some((x, y))
     }
 
-    pub fn kill_invader_at(&mut self, x: usize, y: usize) -> bool {
-        if let Some(idx) = self
-            .army
-            .iter()
-            .position(|invader| invader.x == x && invader.y == y) {
-                self.army.remove(idx);
+    // Enemy shots stop on the bunkers, eroding them
+    pub fn detect_bunker_hits(&mut self, bunkers: &mut Bunkers) -> bool {
+        let mut hit_something = false;
+        for shot in self.shots.iter_mut() {
+            if !shot.exploding && bunkers.hit_at(shot.x, shot.y) {
+                hit_something = true;
+                shot.explode();
+            }
+        }
+
+        hit_something
+    }
+
+    pub fn hit_player_at(&mut self, x: usize, y: usize) -> bool {
+        if let Some(shot) = self
+            .shots
+            .iter_mut()
+            .find(|shot| !shot.exploding && shot.x == x && shot.y == y) {
+                shot.explode();
                 return true
         }
 
@@ -100,12 +389,24 @@ impl Invaders {
 
 impl Drawable for Invaders {
     fn draw(&self, frame: &mut crate::frame::Frame) {
-        for invader in self.army.iter() {
-            if self.move_timer.time_left.as_secs_f32() / self.move_timer.duration.as_secs_f32() > 0.5 {
-                frame[invader.x][invader.y] = "x";
-            } else {
-                frame[invader.x][invader.y] = "+";
+        let animation_frame = if self.move_timer.time_left.as_secs_f32() / self.move_timer.duration.as_secs_f32() > 0.5 { 0 } else { 1 };
+
+        for invader in self.iter() {
+            let glyph = invader.kind.glyphs()[animation_frame];
+            let color = if invader.is_damaged() { Color::Grey } else { invader.kind.color() };
+
+            frame[invader.x][invader.y] = Cell::new(glyph).fg(color);
+        }
+
+        for flash in self.flashes.iter() {
+            if let Some(invader) = self.grid[flash.x][flash.y] {
+                let invader = self.on_board(invader);
+                frame[invader.x][invader.y] = Cell::new(invader.kind.glyphs()[animation_frame]).fg(Color::Red).attr(Attribute::Bold);
             }
         }
+
+        for shot in self.shots.iter() {
+            shot.draw(frame);
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,184 @@
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{config::GameConfig, invaders::InvaderKind, invalid_data};
+
+// First row of the board the formation is placed on, row 0 belongs to the mystery ship
+pub const TOP_ROW: usize = 2;
+
+// Columns kept free on each side, so the formation has room to move
+pub const SIDE_MARGIN: usize = 2;
+
+// Levels shipped with the game, `--level wedge` picks one by name
+const BUNDLED: [(&str, &str); 4] = [
+    ("classic", include_str!("../levels/classic.txt")),
+    ("checker", include_str!("../levels/checker.txt")),
+    ("fortress", include_str!("../levels/fortress.txt")),
+    ("wedge", include_str!("../levels/wedge.txt")),
+];
+
+// An invader formation, drawn as an ASCII grid with the back row on top:
+//
+// # Comments start with '#'
+// 4.4.4.4
+// .3.3.3.
+// 1.1.1.1
+//
+// '1' to '4' are invaders worth 10 to 40 points, 'H' armored ones that take two shots, '.' and ' ' are empty cells.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Level {
+    cells: Vec<Vec<Option<InvaderKind>>>, // By row
+}
+
+impl Level {
+    pub fn bundled_names() -> impl Iterator<Item = &'static str> {
+        BUNDLED.iter().map(|(name, _)| *name)
+    }
+
+    pub fn bundled(name: &str) -> Option<Self> {
+        BUNDLED
+            .iter()
+            .find(|(bundled_name, _)| *bundled_name == name)
+            .map(|(_, text)| Self::parse(text).expect("bundled levels are valid"))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut cells = Vec::new();
+        for (idx, line) in text.lines().enumerate() {
+            if line.starts_with('#') {
+                continue;
+            }
+
+            let row = line
+                .trim_end()
+                .chars()
+                .enumerate()
+                .map(|(col, c)| match c {
+                    '.' | ' ' => Ok(None),
+                    _ => InvaderKind::from_symbol(c)
+                        .map(Some)
+                        .ok_or_else(|| invalid_data(format!("line {}, column {}: unexpected '{}', expected '1'-'4', 'H', '.' or ' '", idx + 1, col + 1, c))),
+                })
+                .collect::<io::Result<Vec<Option<InvaderKind>>>>()?;
+            cells.push(row);
+        }
+
+        // Blank lines only count between rows of invaders
+        let is_empty = |row: &Vec<Option<InvaderKind>>| row.iter().all(|cell| cell.is_none());
+        while cells.last().is_some_and(is_empty) {
+            cells.pop();
+        }
+        let first = cells.iter().position(|row| !is_empty(row)).ok_or_else(|| invalid_data("the level has no invaders".to_string()))?;
+        cells.drain(..first);
+
+        Ok(Self { cells })
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells.iter().map(|row| row.len()).max().unwrap_or(0)
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    // The formation has to sit between the margins and above the army bottom
+    pub fn check_fits(&self, config: &GameConfig) -> io::Result<()> {
+        let max_width = config.num_cols.saturating_sub(2 * SIDE_MARGIN);
+        let max_height = config.army_bottom().saturating_sub(TOP_ROW);
+
+        if self.width() > max_width || self.height() > max_height {
+            return Err(invalid_data(format!(
+                "the level is {}x{}, a {}x{} board fits at most {}x{}",
+                self.width(), self.height(), config.num_cols, config.num_rows, max_width, max_height
+            )));
+        }
+
+        Ok(())
+    }
+
+    // Board position and kind of every invader, with the formation centered horizontally
+    pub fn invaders(&self, config: &GameConfig) -> impl Iterator<Item = (usize, usize, InvaderKind)> + '_ {
+        let left = config.num_cols.saturating_sub(self.width()) / 2;
+
+        self.cells.iter().enumerate().flat_map(move |(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter_map(move |(x, kind)| kind.map(|kind| (left + x, TOP_ROW + y, kind)))
+        })
+    }
+
+    // The grid on a single line, rows separated by '/', for replay files
+    pub fn to_line(&self) -> String {
+        self.cells
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.map(|kind| kind.symbol()).unwrap_or('.')).collect::<String>())
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+
+    pub fn from_line(line: &str) -> io::Result<Self> {
+        Self::parse(&line.replace('/', "\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_unknown_characters_with_their_position() {
+        let err = Level::parse("# Fortress\n1.1\n.x.").unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("line 3, column 2: unexpected 'x'"), "{}", err);
+    }
+
+    #[test]
+    fn refuses_levels_without_invaders() {
+        let err = Level::parse("# Nothing but comments\n\n...\n   \n").unwrap_err();
+
+        assert!(err.to_string().contains("no invaders"), "{}", err);
+    }
+
+    #[test]
+    fn ignores_comments_and_surrounding_blank_lines() {
+        let level = Level::parse("# Two rows\n\n1.1\n\n.2.\n\n").unwrap();
+
+        assert_eq!(level.width(), 3);
+        assert_eq!(level.height(), 3);
+    }
+
+    #[test]
+    fn refuses_levels_too_large_for_the_board() {
+        let config = GameConfig::default(); // 36x7 between the margins and above the army bottom
+
+        assert!(Level::parse(&"1".repeat(36)).unwrap().check_fits(&config).is_ok());
+        assert!(Level::parse(&"1".repeat(37)).unwrap().check_fits(&config).is_err());
+        assert!(Level::parse(&"1\n".repeat(7)).unwrap().check_fits(&config).is_ok());
+
+        let err = Level::parse(&"1\n".repeat(8)).unwrap().check_fits(&config).unwrap_err();
+        assert_eq!(err.to_string(), "the level is 1x8, a 40x20 board fits at most 36x7");
+    }
+
+    #[test]
+    fn bundled_levels_fit_the_classic_board() {
+        for name in Level::bundled_names() {
+            let level = Level::bundled(name).unwrap();
+            assert!(level.check_fits(&GameConfig::default()).is_ok(), "{} doesn't fit", name);
+        }
+    }
+
+    #[test]
+    fn survives_a_round_trip_through_a_line() {
+        for name in Level::bundled_names() {
+            let level = Level::bundled(name).unwrap();
+            assert_eq!(Level::from_line(&level.to_line()).unwrap(), level, "{}", name);
+        }
+    }
+}
@@ -1,8 +1,31 @@
+use std::io;
+
+pub mod audio;
+pub mod autopilot;
+pub mod bindings;
+pub mod bunkers;
+pub mod config;
 pub mod frame;
+pub mod game;
+pub mod highscores;
+pub mod hud;
+pub mod input;
 pub mod render;
 pub mod player;
+pub mod power_ups;
 pub mod shot;
+pub mod weapon;
 pub mod invaders;
+pub mod level;
+pub mod menu;
+pub mod mystery_ship;
+pub mod net;
+pub mod replay;
+pub mod save;
+pub mod session;
+
 
-pub const NUM_ROWS: usize = 20;
-pub const NUM_COLS: usize = 40;
\ No newline at end of file
+// For files and streams that don't hold what they should
+pub(crate) fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
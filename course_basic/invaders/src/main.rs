@@ -1,125 +1,331 @@
 use std::error::Error;
 use std::sync::mpsc;
 use std::time::{Duration, Instant};
-use crossterm::event::{Event, KeyCode};
-use invaders::frame::{new_frame, Drawable};
-use invaders::invaders::Invaders;
-use invaders::player::Player;
-use invaders::render::render;
-use rusty_audio::Audio;
-use std::{io, thread};
+use crossterm::event::Event;
+use invaders::audio::{RustyAudioSink, SilentSink, Sound, SoundSink};
+use invaders::bindings::{Action, KeyBindings};
+use invaders::config::GameConfig;
+use invaders::frame::{new_frame, new_hud, stack, Drawable, Frame};
+use invaders::highscores::HighScores;
+use invaders::hud::PerfCounter;
+use invaders::level::Level;
+use invaders::net::{self, Broadcaster, Connection, FrameReader, MultiRenderer, StreamRenderer};
+use invaders::render::{CrosstermRenderer, MemoryRenderer, Renderer};
+use invaders::input::{InputSource, Keyboard, ReplayInput, Tick, Unattended};
+use invaders::replay::{Recorder, Replay};
+use invaders::save;
+use invaders::session::{Canvas, Session};
+use std::{io::{self, BufReader}, thread};
 use crossterm::{terminal, event};
 use crossterm::terminal::{EnterAlternateScreen, LeaveAlternateScreen};
 use crossterm::cursor::{Show, Hide};
+use crossterm::style::ResetColor;
 use crossterm::ExecutableCommand;
 
+// Frames sent to the render thread, at most 60 per second
+const FRAME_INTERVAL: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+// Value following a `--flag value` pair on the command line
+fn arg_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|idx| args.get(idx + 1))
+        .cloned()
+}
+
+// Draws the frames sent by a host or a broadcast. The second player of a co-op game `plays`: our actions go back to the host.
+fn show_remote(connection: Connection, bindings: &KeyBindings, plays: bool) -> Result<(), Box<dyn Error>> {
+    let mut out = connection.try_clone()?;
+
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    stdout.execute(EnterAlternateScreen)?;
+    stdout.execute(Hide)?;
+
+    let render_handle = thread::spawn(move || {
+        let mut renderer = CrosstermRenderer::stdout();
+        let mut frames = FrameReader::new(BufReader::new(connection));
+        let mut last_frame = Frame::new();
+
+        // Stops when the host goes away, or sends something that isn't a frame
+        while let Some(force) = frames.read_frame()? {
+            renderer.render(&last_frame, frames.frame(), force);
+            last_frame = frames.frame().clone();
+        }
+        Ok::<(), io::Error>(())
+    });
+
+    'gameloop: while !render_handle.is_finished() {
+        while event::poll(Duration::from_millis(5))? {
+            if let Event::Key(key_event) = event::read()? {
+                match bindings.action(key_event.code) {
+                    Some(Action::Quit) => break 'gameloop,
+                    Some(action) if plays && net::send_action(&mut out, action).is_err() => break 'gameloop,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    let _ = out.shutdown();
+    let received = render_handle.join().unwrap();
+
+    stdout.execute(ResetColor)?;
+    stdout.execute(Show)?;
+    stdout.execute(LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+
+    received.map_err(|e| format!("bad frame from the other side: {}", e).into())
+}
+
 fn main() -> Result <(), Box<dyn Error>> {
-    let mut audio = Audio::new();
+    let args: Vec<String> = std::env::args().collect();
+    let headless = args.iter().any(|arg| arg == "--headless"); // No TTY needed, frames are kept in memory
+    let mute = headless || args.iter().any(|arg| arg == "--mute"); // No sound device needed either
+    let debug = args.iter().any(|arg| arg == "--debug"); // Frames and ticks per second in the top right corner of the board
+    let resume = args.iter().any(|arg| arg == "--resume"); // Continue the game saved when quitting
+    let host = arg_value(&args, "--host"); // Co-op: `--host 127.0.0.1:4000` on one terminal...
+    let join_addr = arg_value(&args, "--join"); // ...and `--join 127.0.0.1:4000` on the other
+    let broadcast_addr = arg_value(&args, "--broadcast"); // Spectators: `--broadcast 127.0.0.1:5000` or `--broadcast unix:/tmp/invaders.sock`...
+    let watch_addr = arg_value(&args, "--watch"); // ...and `--watch` the same address to follow the game
+
+    if (host.is_some() || join_addr.is_some()) && (args.iter().any(|arg| arg == "--record" || arg == "--replay" || arg == "--resume") || headless) {
+        return Err("--host and --join can't be combined with --record, --replay, --resume or --headless".into());
+    }
+    if join_addr.is_some() && broadcast_addr.is_some() {
+        return Err("--broadcast belongs on the host, not with --join".into());
+    }
+    if watch_addr.is_some() && args.iter().any(|arg| matches!(arg.as_str(), "--host" | "--join" | "--broadcast" | "--record" | "--replay" | "--resume" | "--headless")) {
+        return Err("--watch only follows someone else's game, it can't be combined with --host, --join, --broadcast, --record, --replay, --resume or --headless".into());
+    }
+    if resume && args.iter().any(|arg| arg == "--record" || arg == "--replay") {
+        return Err("--resume can't be combined with --record or --replay, recordings start from a new game".into());
+    }
+    if args.iter().any(|arg| arg == "--autoplay") && args.iter().any(|arg| arg == "--replay") {
+        return Err("--autoplay can't be combined with --replay, the recording tells whether the autopilot played".into());
+    }
+    if resume && headless {
+        return Err("--resume waits on the pause menu for the player, it can't be combined with --headless".into());
+    }
+
+    let save_path = save::default_path();
+    let resumed = match &save_path {
+        Some(path) if resume => Some(save::load_game(path).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => format!("--resume: there is no saved game at {}", path.display()),
+            _ => format!("--resume {}: {}", path.display(), e),
+        })?),
+        None if resume => return Err("--resume: no data directory to load the saved game from".into()),
+        _ => None,
+    };
+
+    let replay = match arg_value(&args, "--replay") {
+        Some(path) => Some(Replay::load(path)?),
+        None => None,
+    };
+
+    // Replays go through the same menus, without touching the saved game and the high scores
+    let persist = replay.is_none();
+
+    // The autopilot plays instead of the keyboard, e.g. for balancing runs. Replays of its games bring it back.
+    let autoplay = match &replay {
+        Some(replay) => replay.autoplay(),
+        None => args.iter().any(|arg| arg == "--autoplay"),
+    };
+
+    // Headless runs have nobody to press a key, they play a single game. Replays start the way their recording did.
+    let single_game = match &replay {
+        Some(replay) => replay.single_game(),
+        None => headless,
+    };
 
-    audio.add("explode", "sounds/explode.wav");
-    audio.add("lose", "sounds/lose.wav");
-    audio.add("move", "sounds/move.wav");
-    audio.add("pew", "sounds/pew.wav");
-    audio.add("startup", "sounds/startup.wav");
-    audio.add("win", "sounds/win.wav");
+    // Board size: `--size 80x24`, `--size auto` to fill the terminal, the classic 40x20 otherwise
+    let config = match (&resumed, &replay, arg_value(&args, "--size")) {
+        (Some(game), _, _) => *game.config(),
+        (None, Some(replay), _) => *replay.config(),
+        (None, None, Some(size)) if size == "auto" => GameConfig::from_terminal()?,
+        (None, None, Some(size)) => GameConfig::parse_size(&size).map_err(|e| format!("--size: {}", e))?,
+        (None, None, None) => GameConfig::default(),
+    };
 
-    audio.play("startup");
+    // Invader formation: a bundled level by name or a level file, the classic formation otherwise
+    let level = match (&resumed, &replay, arg_value(&args, "--level")) {
+        (Some(game), _, _) => game.level().cloned(),
+        (None, Some(replay), _) => replay.level().cloned(),
+        (None, None, Some(name)) => match Level::bundled(&name) {
+            Some(level) => Some(level),
+            None => Some(Level::load(&name).map_err(|e| format!("--level '{}': {} (bundled levels: {})", name, e, Level::bundled_names().collect::<Vec<_>>().join(", ")))?),
+        },
+        (None, None, None) => None,
+    };
+    if let Some(level) = &level {
+        level.check_fits(&config).map_err(|e| format!("--level: {}", e))?;
+    }
+
+    // Every game is seeded from this seed, so a replay only needs it
+    let seed = replay.as_ref().map(|replay| replay.seed()).unwrap_or_else(rand::random);
+
+    // Replays show the table they were recorded with, whatever it holds by now
+    let high_scores_path = HighScores::default_path();
+    let high_scores = match (&replay, &high_scores_path) {
+        (Some(replay), _) => replay.high_scores().clone(),
+        (None, Some(path)) => HighScores::load(path)?,
+        (None, None) => HighScores::default(),
+    };
+
+    let mut recorder = match arg_value(&args, "--record") {
+        Some(path) => Some(Recorder::create(path, &config, seed, level.as_ref(), autoplay, single_game, &high_scores)?),
+        None => None,
+    };
+
+    let bindings = match KeyBindings::default_path() {
+        Some(path) => KeyBindings::load(path)?,
+        None => KeyBindings::default(),
+    };
+
+    if let Some(addr) = join_addr {
+        return show_remote(Connection::Tcp(net::join_game(addr.as_str())?), &bindings, true);
+    }
+    if let Some(addr) = watch_addr {
+        return show_remote(Connection::connect(&addr).map_err(|e| format!("--watch {}: {}", addr, e))?, &bindings, false);
+    }
+
+    let broadcaster = match &broadcast_addr {
+        Some(addr) => Some(Broadcaster::bind(addr).map_err(|e| format!("--broadcast {}: {}", addr, e))?),
+        None => None,
+    };
+
+    // The other player's actions come in on their own connection, our frames go out on it
+    let (remote, remote_actions) = match &host {
+        Some(addr) => {
+            println!("Waiting for the second player on {}...", addr);
+            let stream = net::accept_player(addr.as_str())?;
+            let actions = net::receive_actions(stream.try_clone()?);
+            (Some(stream), Some(actions))
+        }
+        None => (None, None),
+    };
+    let num_players = if remote.is_some() { 2 } else { 1 };
+
+    let mut audio: Box<dyn SoundSink> = if mute {
+        Box::new(SilentSink)
+    } else {
+        Box::new(RustyAudioSink::new())
+    };
+
+    audio.play(Sound::Startup);
 
     // Terminal
     let mut stdout = io::stdout();
-    terminal::enable_raw_mode()?; // ? -> Crash if something is wrong
-    stdout.execute(EnterAlternateScreen)?; // Enter in an alternative screen while playing the game
-    stdout.execute(Hide)?; // Hide cursor
+    if !headless {
+        terminal::enable_raw_mode()?; // ? -> Crash if something is wrong
+        stdout.execute(EnterAlternateScreen)?; // Enter in an alternative screen while playing the game
+        stdout.execute(Hide)?; // Hide cursor
+    }
 
     // Render loop in a separate thread
     let (render_tx, render_rx) = mpsc::channel();
+    let (recycle_tx, recycle_rx) = mpsc::channel(); // Rendered frames come back to be drawn on again
+    let mut renderers: Vec<Box<dyn Renderer + Send>> = match headless {
+        true => vec![Box::new(MemoryRenderer::new())],
+        false => vec![Box::new(CrosstermRenderer::stdout())],
+    };
+    if let Some(stream) = remote {
+        renderers.push(Box::new(StreamRenderer::new(stream)));
+    }
+    if let Some(broadcaster) = broadcaster {
+        renderers.push(Box::new(broadcaster));
+    }
+    let mut renderer: Box<dyn Renderer + Send> = match renderers.len() {
+        1 => renderers.remove(0),
+        _ => Box::new(MultiRenderer::new(renderers)),
+    };
 
     let render_handle = thread::spawn(move || {
-        let mut last_frame = new_frame();
-        let mut stdout = io::stdout();
-        render(&mut stdout, &last_frame, &last_frame, true);
-
-        loop {
-            let curr_frame = match render_rx.recv() {
-                Ok(x) => x,
-                Err(_) => break,
-            };
-            render(&mut stdout, &last_frame, &curr_frame, false);
-            last_frame = curr_frame;
+        let mut last_frame = stack(&new_hud(&config), &new_frame(&config));
+        renderer.render(&last_frame, &last_frame, true);
+
+        while let Ok(curr_frame) = render_rx.recv() {
+            renderer.render(&last_frame, &curr_frame, false);
+            let _ = recycle_tx.send(std::mem::replace(&mut last_frame, curr_frame));
         }
     });
 
-    let mut player = Player::new();
-    let mut instant = Instant::now();
-    let mut invaders = Invaders::new();
+    // Replays feed back recorded inputs and deltas, so the same frames are produced again
+    let mut input: Box<dyn InputSource> = match replay {
+        Some(replay) => Box::new(ReplayInput::new(replay, !headless)),
+        None if headless => Box::new(Unattended),
+        None => Box::new(Keyboard::new(&config, &bindings)?),
+    };
+    let mut last_render = Instant::now();
+    let mut perf = PerfCounter::new();
+    let mut canvas = Canvas::new(&config);
+
+    let session = Session::new(&config, level, num_players, seed, bindings)
+        .with_high_scores(high_scores, high_scores_path)
+        .with_save_path(save_path)
+        .with_persist(persist)
+        .with_autoplay(autoplay);
+    let mut session = match resumed {
+        Some(game) => session.resume(game),
+        None if single_game => session.single_game(),
+        None => session,
+    };
 
     // Game loop
     'gameloop: loop {
-        let delta = instant.elapsed();
-        instant = Instant::now();
-
-        let mut curr_frame = new_frame();
+        // Out of input: the end of a replay, or the player quit while the terminal was too small for the menus
+        let Some(ticks) = input.next_ticks(session.is_typing())? else {
+            session.leave()?;
+            break 'gameloop;
+        };
 
-        while event::poll(Duration::default())? {
-            if let Event::Key(key_event) = event::read()? {
-                match key_event.code {
-                    KeyCode::Esc | KeyCode::Char('q') => {
-                        audio.play("lose");
-                        break 'gameloop;
-                    },
-                    KeyCode::Left => player.move_left(),
-                    KeyCode::Right => player.move_right(),
-                    KeyCode::Char(' ') => {
-                        if player.shoot() {
-                            audio.play("pew");
-                        }
-                    }
-                    _ => {}
-                }
+        for Tick { delta, inputs } in ticks {
+            if let Some(recorder) = recorder.as_mut() {
+                recorder.record_tick(delta, &inputs)?;
             }
-        }
 
-        // Updates
-        player.update(delta);
+            let remote: Vec<Action> = remote_actions.iter().flat_map(|actions| actions.try_iter()).collect();
+            session.tick(delta, &inputs, &remote, audio.as_mut())?;
+            perf.count_tick();
 
-        if invaders.update(delta) {
-            audio.play("move");
+            if session.has_quit() {
+                break 'gameloop;
+            }
         }
 
-        if player.detect_hits(&mut invaders) {
-            audio.play("explode");
+        // Draw & render, no more often than the terminal can show
+        if last_render.elapsed() < FRAME_INTERVAL {
+            continue;
         }
+        last_render = Instant::now();
 
-        // Draw & render
-        let drawables: Vec<&dyn Drawable> = vec![&player, &invaders];
-
-        for drawable in drawables {
-            drawable.draw(&mut curr_frame);
+        let mut curr_frame = recycle_rx.try_recv().unwrap_or_default();
+        session.draw(&mut canvas, &mut curr_frame);
+        perf.count_frame();
+        if debug {
+            perf.draw(&mut curr_frame);
         }
         let _ = render_tx.send(curr_frame);
-        thread::sleep(Duration::from_millis(1)); // We put a sleep here as the game loop is much faster than rendering
-
-        // Win or lose
-        if invaders.all_killed() {
-            audio.play("win");
-            break 'gameloop;
-        }
-        
-        if invaders.reached_bottom() {
-            audio.play("lose");
-            break 'gameloop;
-        }
     }
 
+    if headless && session.game.is_over() {
+        println!("score {} wave {}", session.game.score(), session.game.wave); // For balancing runs
+    }
 
     // Cleanup
+    if let Some(recorder) = recorder {
+        recorder.finish()?;
+    }
     drop(render_tx); // Remove transmitting channel, this will trigger an error on the receiver causing the loop to break
     render_handle.join().unwrap();
 
     audio.wait();
-    stdout.execute(Show)?;
-    stdout.execute(LeaveAlternateScreen)?;
-    terminal::disable_raw_mode()?;
+    if !headless {
+        stdout.execute(ResetColor)?;
+        stdout.execute(Show)?;
+        stdout.execute(LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+    }
 
     Ok(())
 
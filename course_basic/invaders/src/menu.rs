@@ -0,0 +1,114 @@
+use crossterm::style::Color;
+
+use crate::{bindings::{Action, KeyBindings}, frame::{draw_text_centered, Drawable, Frame}, highscores::{HighScores, InitialsEntry}};
+
+pub enum GameState {
+    Title,
+    Playing,
+    Demo, // Attract mode, the autopilot plays until a key is pressed
+    Paused,
+    SavePrompt, // Quitting a game in progress
+    GameOver(GameOver),
+}
+
+pub struct GameOver {
+    pub score: u32,
+    pub entry: Option<InitialsEntry>, // Only while the initials of a new high score are typed in
+}
+
+impl GameOver {
+    pub fn new(score: u32, high_scores: &HighScores) -> Self {
+        Self {
+            score,
+            entry: high_scores.qualifies(score).then(|| InitialsEntry::new(score)),
+        }
+    }
+}
+
+fn middle_row(frame: &Frame) -> usize {
+    frame.first().map(|col| col.len()).unwrap_or(0) / 2
+}
+
+pub struct TitleScreen<'a> {
+    pub high_scores: &'a HighScores,
+    pub bindings: &'a KeyBindings,
+}
+
+impl Drawable for TitleScreen<'_> {
+    fn draw(&self, frame: &mut Frame) {
+        let middle = middle_row(frame);
+
+        draw_text_centered(frame, middle - 3, "SPACE INVADERS", Color::Green);
+        draw_text_centered(frame, middle, &format!("{:<6} PLAY  ", "ENTER"), Color::White);
+        draw_text_centered(frame, middle + 1, &format!("{:<6} WEAPON", self.bindings.label(Action::SwitchWeapon)), Color::White);
+        draw_text_centered(frame, middle + 2, &format!("{:<6} PAUSE ", self.bindings.label(Action::Pause)), Color::White);
+        draw_text_centered(frame, middle + 3, &format!("{:<6} QUIT  ", self.bindings.label(Action::Quit)), Color::White);
+
+        if let Some(best) = self.high_scores.entries().first() {
+            draw_text_centered(frame, middle + 5, &format!("HI {} {}", best.initials, best.score), Color::Yellow);
+        }
+    }
+}
+
+// Drawn over the autopilot's game in attract mode
+pub struct DemoBanner;
+
+impl Drawable for DemoBanner {
+    fn draw(&self, frame: &mut Frame) {
+        let middle = middle_row(frame);
+
+        draw_text_centered(frame, middle, " DEMO ", Color::Yellow);
+        draw_text_centered(frame, middle + 1, " PRESS ANY KEY ", Color::White);
+    }
+}
+
+// Drawn over the frozen game
+pub struct PauseMenu<'a> {
+    pub bindings: &'a KeyBindings,
+}
+
+impl Drawable for PauseMenu<'_> {
+    fn draw(&self, frame: &mut Frame) {
+        let middle = middle_row(frame);
+
+        draw_text_centered(frame, middle - 1, "    PAUSED     ", Color::Yellow);
+        draw_text_centered(frame, middle, &format!(" {:<6} RESUME ", self.bindings.label(Action::Pause)), Color::White);
+        draw_text_centered(frame, middle + 1, &format!(" {:<6} MENU   ", self.bindings.label(Action::Quit)), Color::White);
+    }
+}
+
+// Drawn over the frozen game when quitting
+pub struct SavePrompt<'a> {
+    pub bindings: &'a KeyBindings,
+}
+
+impl Drawable for SavePrompt<'_> {
+    fn draw(&self, frame: &mut Frame) {
+        let middle = middle_row(frame);
+
+        draw_text_centered(frame, middle - 1, "  SAVE GAME?  ", Color::Yellow);
+        draw_text_centered(frame, middle, " Y      YES    ", Color::White);
+        draw_text_centered(frame, middle + 1, " N      NO     ", Color::White);
+        draw_text_centered(frame, middle + 2, &format!(" {:<6} BACK   ", self.bindings.label(Action::Quit)), Color::White);
+    }
+}
+
+pub struct GameOverScreen<'a> {
+    pub game_over: &'a GameOver,
+    pub high_scores: &'a HighScores,
+    pub bindings: &'a KeyBindings,
+}
+
+impl Drawable for GameOverScreen<'_> {
+    fn draw(&self, frame: &mut Frame) {
+        if let Some(entry) = &self.game_over.entry {
+            entry.draw(frame);
+            return;
+        }
+
+        self.high_scores.draw(frame);
+
+        let num_rows = frame.first().map(|col| col.len()).unwrap_or(0);
+        draw_text_centered(frame, num_rows - 1, &format!("ENTER AGAIN  {} MENU", self.bindings.label(Action::Quit)), Color::White);
+    }
+}
@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use crossterm::style::{Attribute, Color};
+use rand::Rng;
+use rusty_time::timer::Timer;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::GameConfig, frame::{Cell, Drawable}};
+
+const SHIP_GLYPHS: [&str; 3] = ["<", "=", ">"];
+const SHIP_WIDTH: usize = SHIP_GLYPHS.len();
+const BONUS_POINTS: [u32; 4] = [50, 100, 150, 300];
+
+#[derive(Serialize, Deserialize)]
+struct Ship {
+    x: usize, // Leftmost cell
+    direction: i32,
+    bonus: u32,
+}
+
+// UFO crossing the top row every now and then, worth a random bonus when shot down
+#[derive(Serialize, Deserialize)]
+pub struct MysteryShip {
+    ship: Option<Ship>,
+    #[serde(with = "crate::save::timer")]
+    spawn_timer: Timer,
+    #[serde(with = "crate::save::timer")]
+    move_timer: Timer,
+    config: GameConfig,
+}
+
+impl MysteryShip {
+    pub fn new(config: &GameConfig) -> Self {
+        Self {
+            ship: None,
+            spawn_timer: Timer::from_millis(20000),
+            move_timer: Timer::from_millis(150),
+            config: *config,
+        }
+    }
+
+    // Returns true when a ship appears, so its sound can be played
+    pub fn update<R: Rng>(&mut self, delta: Duration, rng: &mut R) -> bool {
+        match self.ship.as_mut() {
+            Some(ship) => {
+                self.move_timer.update(delta);
+                if self.move_timer.ready {
+                    self.move_timer.reset();
+
+                    let new_x = ship.x as i32 + ship.direction;
+                    if new_x < 0 || new_x as usize + SHIP_WIDTH > self.config.num_cols {
+                        self.ship = None; // Missed, it flew off the board
+                    } else {
+                        ship.x = new_x as usize;
+                    }
+                }
+
+                false
+            }
+            None => {
+                self.spawn_timer.update(delta);
+                if !self.spawn_timer.ready {
+                    return false;
+                }
+
+                let (x, direction) = if rng.gen_bool(0.5) {
+                    (0, 1)
+                } else {
+                    (self.config.num_cols - SHIP_WIDTH, -1)
+                };
+                let bonus = BONUS_POINTS[rng.gen_range(0..BONUS_POINTS.len())];
+
+                self.ship = Some(Ship { x, direction, bonus });
+                self.move_timer.reset();
+
+                // Counts down once this ship is gone, the next one shows up 15 to 30 seconds later
+                self.spawn_timer = Timer::from_millis(rng.gen_range(15000..30000));
+                true
+            }
+        }
+    }
+
+    // Returns the bonus if the ship occupies the given cell
+    pub fn kill_at(&mut self, x: usize, y: usize) -> Option<u32> {
+        let ship = self.ship.as_ref()?;
+        if y != 0 || x < ship.x || x >= ship.x + SHIP_WIDTH {
+            return None;
+        }
+
+        self.ship.take().map(|ship| ship.bonus)
+    }
+}
+
+impl Drawable for MysteryShip {
+    fn draw(&self, frame: &mut crate::frame::Frame) {
+        if let Some(ship) = &self.ship {
+            for (i, glyph) in SHIP_GLYPHS.iter().enumerate() {
+                frame[ship.x + i][0] = Cell::new(glyph).fg(Color::Red).attr(Attribute::Bold);
+            }
+        }
+    }
+}
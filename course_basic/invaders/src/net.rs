@@ -0,0 +1,468 @@
+use std::{fs, io::{self, BufRead, BufReader, Read, Write}, net::{TcpListener, TcpStream, ToSocketAddrs}, path::PathBuf, sync::mpsc::{self, Receiver, Sender}, thread, time::Duration};
+#[cfg(unix)]
+use std::os::unix::{fs::FileTypeExt, net::{UnixListener, UnixStream}};
+
+use crossterm::style::{Attribute, Attributes, Color};
+
+use crate::{bindings::Action, frame::{glyph, Cell, Frame}, render::Renderer, invalid_data};
+
+// Frames travel as text:
+//
+// frame <force 0|1> <num_cols> <num_rows> <num_cells>
+// <x> <y> <code point> <fg> <bg> <attributes>    (once per changed cell)
+//
+// and actions go the other way, one `Action::name` per line.
+
+// A player or viewer that takes longer than this to accept a frame is dropped, instead of holding up the game
+const WRITE_TIMEOUT: Duration = Duration::from_millis(200);
+
+// Frames with more columns or rows than this are refused, instead of allocating whatever the other side asks for
+const MAX_FRAME_SIDE: usize = 1000;
+
+// Colors are sent as their index in this table
+const COLORS: [Color; 17] = [
+    Color::Reset,
+    Color::Black,
+    Color::DarkGrey,
+    Color::Red,
+    Color::DarkRed,
+    Color::Green,
+    Color::DarkGreen,
+    Color::Yellow,
+    Color::DarkYellow,
+    Color::Blue,
+    Color::DarkBlue,
+    Color::Magenta,
+    Color::DarkMagenta,
+    Color::Cyan,
+    Color::DarkCyan,
+    Color::White,
+    Color::Grey,
+];
+
+fn color_index(color: Color) -> usize {
+    COLORS.iter().position(|c| *c == color).unwrap_or(COLORS.len() - 2) // White when we can't send it
+}
+
+fn attributes_bits(attrs: Attributes) -> u32 {
+    Attribute::iterator()
+        .filter(|attr| attrs.has(*attr))
+        .fold(0, |bits, attr| bits | attr.bytes())
+}
+
+fn attributes_from_bits(bits: u32) -> Attributes {
+    let mut attrs = Attributes::default();
+    for attr in Attribute::iterator().filter(|attr| bits & attr.bytes() != 0) {
+        attrs.set(attr);
+    }
+    attrs
+}
+
+// Sends the changed cells of every frame down a stream instead of drawing them
+pub struct StreamRenderer<W: Write> {
+    out: W,
+    connected: bool,
+}
+
+impl<W: Write> StreamRenderer<W> {
+    pub fn new(out: W) -> Self {
+        Self { out, connected: true }
+    }
+
+    // False once a write failed, the other side went away
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    fn send(&mut self, last_frame: &Frame, curr_frame: &Frame, force: bool) -> io::Result<()> {
+        let num_rows = curr_frame.first().map(|col| col.len()).unwrap_or(0);
+        let mut cells = Vec::new();
+
+        for (x, col) in curr_frame.iter().enumerate() {
+            for (y, cell) in col.iter().enumerate() {
+                if force || last_frame.get(x).and_then(|col| col.get(y)) != Some(cell) {
+                    let c = cell.glyph.chars().next().unwrap_or(' ');
+                    cells.push(format!("{} {} {} {} {} {}\n", x, y, c as u32, color_index(cell.fg), color_index(cell.bg), attributes_bits(cell.attrs)));
+                }
+            }
+        }
+
+        // Nothing changed, nothing to send
+        if cells.is_empty() && !force {
+            return Ok(());
+        }
+
+        let mut message = format!("frame {} {} {} {}\n", force as u8, curr_frame.len(), num_rows, cells.len());
+        message.extend(cells);
+
+        self.out.write_all(message.as_bytes())?;
+        self.out.flush()
+    }
+}
+
+impl<W: Write> Renderer for StreamRenderer<W> {
+    fn render(&mut self, last_frame: &Frame, curr_frame: &Frame, force: bool) {
+        if self.connected && self.send(last_frame, curr_frame, force).is_err() {
+            self.connected = false;
+        }
+    }
+}
+
+// Rebuilds the frames sent by a `StreamRenderer`
+pub struct FrameReader<R: BufRead> {
+    input: R,
+    frame: Frame,
+}
+
+impl<R: BufRead> FrameReader<R> {
+    pub fn new(input: R) -> Self {
+        Self { input, frame: Frame::new() }
+    }
+
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    // Applies the next frame, returns whether it has to be redrawn from scratch, or None once the stream ended
+    pub fn read_frame(&mut self) -> io::Result<Option<bool>> {
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let header: Vec<usize> = line
+            .strip_prefix("frame ")
+            .map(|fields| fields.split_whitespace().filter_map(|field| field.parse().ok()).collect())
+            .unwrap_or_default();
+        let [force, num_cols, num_rows, num_cells] = header[..] else {
+            return Err(invalid_data(format!("expected a frame header, got '{}'", line.trim())));
+        };
+        if num_cols > MAX_FRAME_SIDE || num_rows > MAX_FRAME_SIDE {
+            return Err(invalid_data(format!("a {}x{} frame is larger than {}x{}", num_cols, num_rows, MAX_FRAME_SIDE, MAX_FRAME_SIDE)));
+        }
+        if num_cells > num_cols * num_rows {
+            return Err(invalid_data(format!("{} cells don't fit in a {}x{} frame", num_cells, num_cols, num_rows)));
+        }
+
+        // A new size always comes with a full redraw
+        let force = force == 1 || self.frame.len() != num_cols || self.frame.first().map(|col| col.len()) != Some(num_rows);
+        if force {
+            self.frame = vec![vec![Cell::default(); num_rows]; num_cols];
+        }
+
+        for _ in 0..num_cells {
+            line.clear();
+            self.input.read_line(&mut line)?;
+
+            let fields: Vec<u32> = line.split_whitespace().filter_map(|field| field.parse().ok()).collect();
+            let [x, y, code, fg, bg, attrs] = fields[..] else {
+                return Err(invalid_data(format!("expected a cell, got '{}'", line.trim())));
+            };
+
+            let cell = self
+                .frame
+                .get_mut(x as usize)
+                .and_then(|col| col.get_mut(y as usize))
+                .ok_or_else(|| invalid_data(format!("cell {} {} is outside of the frame", x, y)))?;
+
+            *cell = Cell {
+                glyph: glyph(char::from_u32(code).unwrap_or('?')),
+                fg: COLORS.get(fg as usize).copied().unwrap_or(Color::White),
+                bg: COLORS.get(bg as usize).copied().unwrap_or(Color::Black),
+                attrs: attributes_from_bits(attrs),
+            };
+        }
+
+        Ok(Some(force))
+    }
+}
+
+// Host side of a co-op game: waits for the second player to connect
+pub fn accept_player<A: ToSocketAddrs>(addr: A) -> io::Result<TcpStream> {
+    accept_on(TcpListener::bind(addr)?)
+}
+
+fn accept_on(listener: TcpListener) -> io::Result<TcpStream> {
+    let (stream, _) = listener.accept()?;
+    stream.set_nodelay(true)?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    Ok(stream)
+}
+
+pub fn join_game<A: ToSocketAddrs>(addr: A) -> io::Result<TcpStream> {
+    let stream = TcpStream::connect(addr)?;
+    stream.set_nodelay(true)?;
+    Ok(stream)
+}
+
+// Actions sent by the remote player, read on their own thread so the game loop never blocks
+pub fn receive_actions(stream: TcpStream) -> Receiver<Action> {
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            if let Some(action) = Action::from_name(line.trim()) {
+                if tx.send(action).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    rx
+}
+
+pub fn send_action<W: Write>(out: &mut W, action: Action) -> io::Result<()> {
+    writeln!(out, "{}", action.name())?;
+    out.flush()
+}
+
+// Draws on more than one backend, e.g. the local terminal and a remote player
+pub struct MultiRenderer {
+    renderers: Vec<Box<dyn Renderer + Send>>,
+}
+
+impl MultiRenderer {
+    pub fn new(renderers: Vec<Box<dyn Renderer + Send>>) -> Self {
+        Self { renderers }
+    }
+}
+
+impl Renderer for MultiRenderer {
+    fn render(&mut self, last_frame: &Frame, curr_frame: &Frame, force: bool) {
+        for renderer in self.renderers.iter_mut() {
+            renderer.render(last_frame, curr_frame, force);
+        }
+    }
+}
+
+// Either end of a TCP connection, or of a Unix socket for addresses like `unix:/tmp/invaders.sock`
+pub enum Connection {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Connection {
+    pub fn connect(addr: &str) -> io::Result<Self> {
+        match addr.strip_prefix("unix:") {
+            #[cfg(unix)]
+            Some(path) => Ok(Connection::Unix(UnixStream::connect(path)?)),
+            #[cfg(not(unix))]
+            Some(_) => Err(io::Error::new(io::ErrorKind::Unsupported, "Unix sockets are not available on this platform")),
+            None => Ok(Connection::Tcp(join_game(addr)?)),
+        }
+    }
+
+    pub fn try_clone(&self) -> io::Result<Self> {
+        match self {
+            Connection::Tcp(stream) => Ok(Connection::Tcp(stream.try_clone()?)),
+            #[cfg(unix)]
+            Connection::Unix(stream) => Ok(Connection::Unix(stream.try_clone()?)),
+        }
+    }
+
+    // Both directions, a thread blocked reading from the connection wakes up
+    pub fn shutdown(&self) -> io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => stream.shutdown(std::net::Shutdown::Both),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.shutdown(std::net::Shutdown::Both),
+        }
+    }
+
+    fn set_write_timeout(&self, timeout: Duration) -> io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => stream.set_write_timeout(Some(timeout)),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.set_write_timeout(Some(timeout)),
+        }
+    }
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Connection::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Connection::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+// Hands the connections accepted on their own thread over to the game
+fn forward_connections<I: Iterator<Item = io::Result<Connection>>>(incoming: I, viewers: Sender<Connection>) {
+    for connection in incoming.flatten() {
+        if viewers.send(connection).is_err() {
+            break; // Nobody broadcasts anymore
+        }
+    }
+}
+
+// Streams every frame to any number of read-only viewers, who can come and go during the game
+pub struct Broadcaster {
+    viewers: Vec<StreamRenderer<Connection>>,
+    new_viewers: Receiver<Connection>,
+    socket_path: Option<PathBuf>, // Removed with the broadcast
+}
+
+impl Broadcaster {
+    pub fn bind(addr: &str) -> io::Result<Self> {
+        let (tx, new_viewers) = mpsc::channel();
+        let mut socket_path = None;
+
+        match addr.strip_prefix("unix:") {
+            #[cfg(unix)]
+            Some(path) => {
+                // Left behind by a broadcast that didn't end well, nobody answers on it anymore. One that does is still
+                // running and keeps its viewers.
+                if fs::metadata(path).map(|metadata| metadata.file_type().is_socket()).unwrap_or(false) {
+                    match UnixStream::connect(path) {
+                        Ok(_) => return Err(io::Error::new(io::ErrorKind::AddrInUse, "another broadcast is running on this socket")),
+                        Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => fs::remove_file(path)?,
+                        Err(_) => {}
+                    }
+                }
+
+                let listener = UnixListener::bind(path)?;
+                socket_path = Some(PathBuf::from(path));
+                thread::spawn(move || forward_connections(listener.incoming().map(|stream| stream.map(Connection::Unix)), tx));
+            }
+            #[cfg(not(unix))]
+            Some(_) => return Err(io::Error::new(io::ErrorKind::Unsupported, "Unix sockets are not available on this platform")),
+            None => {
+                let listener = TcpListener::bind(addr)?;
+                let incoming = move |stream: io::Result<TcpStream>| {
+                    let stream = stream?;
+                    stream.set_nodelay(true)?;
+                    Ok(Connection::Tcp(stream))
+                };
+                thread::spawn(move || forward_connections(listener.incoming().map(incoming), tx));
+            }
+        }
+
+        Ok(Self { viewers: Vec::new(), new_viewers, socket_path })
+    }
+}
+
+impl Renderer for Broadcaster {
+    fn render(&mut self, last_frame: &Frame, curr_frame: &Frame, force: bool) {
+        for viewer in self.viewers.iter_mut() {
+            viewer.render(last_frame, curr_frame, force);
+        }
+
+        // Newcomers start with the whole frame
+        for connection in self.new_viewers.try_iter() {
+            if connection.set_write_timeout(WRITE_TIMEOUT).is_ok() {
+                let mut viewer = StreamRenderer::new(connection);
+                viewer.render(curr_frame, curr_frame, true);
+                self.viewers.push(viewer);
+            }
+        }
+
+        self.viewers.retain(|viewer| viewer.is_connected());
+    }
+}
+
+impl Drop for Broadcaster {
+    fn drop(&mut self) {
+        if let Some(path) = &self.socket_path {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Shutdown;
+
+    use super::*;
+
+    // Over loopback, the way a co-op game runs on a single machine
+    #[test]
+    fn plays_over_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let host = thread::spawn(move || accept_on(listener));
+        let mut player = join_game(addr).unwrap();
+        let host = host.join().unwrap().unwrap();
+
+        let actions = receive_actions(host.try_clone().unwrap());
+        send_action(&mut player, Action::MoveLeft).unwrap();
+        send_action(&mut player, Action::Fire).unwrap();
+        assert_eq!(actions.recv_timeout(Duration::from_secs(5)), Ok(Action::MoveLeft));
+        assert_eq!(actions.recv_timeout(Duration::from_secs(5)), Ok(Action::Fire));
+
+        let mut first = vec![vec![Cell::default(); 3]; 4];
+        first[1][2] = Cell::new(glyph('A')).fg(Color::Red);
+        let mut second = first.clone();
+        second[2][0] = Cell::new(glyph('#')).fg(Color::Cyan);
+
+        let mut renderer = StreamRenderer::new(host.try_clone().unwrap());
+        renderer.render(&first, &first, true);
+        renderer.render(&first, &second, false);
+        renderer.render(&second, &second, false); // Nothing changed, nothing sent
+        host.shutdown(Shutdown::Write).unwrap(); // The actions thread keeps its end open
+
+        let mut frames = FrameReader::new(BufReader::new(player));
+        assert_eq!(frames.read_frame().unwrap(), Some(true));
+        assert_eq!(frames.frame(), &first);
+        assert_eq!(frames.read_frame().unwrap(), Some(false));
+        assert_eq!(frames.frame(), &second);
+        assert_eq!(frames.read_frame().unwrap(), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn leaves_running_broadcasts_alone() {
+        let path = std::env::temp_dir().join(format!("invaders-broadcast-{}.sock", std::process::id()));
+        let addr = format!("unix:{}", path.display());
+
+        // A socket file nobody listens on anymore is taken over
+        drop(UnixListener::bind(&path).unwrap());
+        let running = Broadcaster::bind(&addr).unwrap();
+
+        let err = Broadcaster::bind(&addr).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        assert!(UnixStream::connect(&path).is_ok());
+
+        drop(running);
+        assert!(!path.exists());
+    }
+
+    fn read_error(stream: &str) -> String {
+        FrameReader::new(stream.as_bytes()).read_frame().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn refuses_oversized_frames() {
+        assert_eq!(read_error("frame 1 1001 10 0\n"), "a 1001x10 frame is larger than 1000x1000");
+        assert_eq!(read_error("frame 1 2 2 5\n"), "5 cells don't fit in a 2x2 frame");
+    }
+
+    #[test]
+    fn refuses_bad_cells() {
+        assert_eq!(read_error("frame 1 2 2 1\nnot a cell\n"), "expected a cell, got 'not a cell'");
+        assert_eq!(read_error("frame 1 2 2 1\n5 0 65 0 0 0\n"), "cell 5 0 is outside of the frame");
+        assert_eq!(read_error("hello\n"), "expected a frame header, got 'hello'");
+    }
+}
@@ -1,73 +1,256 @@
 use std::time::Duration;
 
-use crate::{NUM_COLS, NUM_ROWS, frame::Drawable, shot::Shot, invaders::Invaders};
+use crossterm::style::{Attribute, Color};
+use rand::Rng;
+use rusty_time::timer::Timer;
+use serde::{Deserialize, Serialize};
 
+use crate::{bindings::Action, bunkers::Bunkers, config::GameConfig, frame::{Cell, Drawable}, shot::Shot, invaders::{Hit, Invaders}, mystery_ship::MysteryShip, power_ups::{PowerUpKind, PowerUps}, weapon::{self, Weapon}};
+
+pub const START_LIVES: u32 = 3;
+
+// Rapid fire allows this many more shots in the air, and halves the cooldown
+const RAPID_FIRE_EXTRA_SHOTS: usize = 3;
+
+// One color per player in co-op games
+const PLAYER_COLORS: [Color; 4] = [Color::Green, Color::Cyan, Color::Magenta, Color::Yellow];
+
+// A caught power-up, until its timer runs out
+#[derive(Serialize, Deserialize)]
+pub struct Effect {
+    pub kind: PowerUpKind,
+    #[serde(with = "crate::save::timer")]
+    pub timer: Timer,
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Player {
     x: usize,
     y: usize,
+    spawn_x: usize,
+    color: Color,
     shots: Vec<Shot>,
+    lives: u32,
+    score: u32,
+    #[serde(with = "crate::save::optional_timer")]
+    respawn_timer: Option<Timer>, // Invulnerable while it runs
+    effects: Vec<Effect>,
+    #[serde(with = "crate::weapon::by_name_serde")]
+    weapon: &'static dyn Weapon,
+    reload: Duration, // Until the weapon can fire again
+    config: GameConfig,
 }
 
 impl Player {
-    pub fn new() -> Self {
+    pub fn new(config: &GameConfig) -> Self {
+        Self::for_slot(config, 0, 1)
+    }
+
+    // Players of a co-op game start evenly spread along the bottom row
+    pub fn for_slot(config: &GameConfig, slot: usize, num_slots: usize) -> Self {
+        let spawn_x = config.num_cols * (slot + 1) / (num_slots + 1);
+
         Self {
-            x: NUM_COLS / 2,
-            y: NUM_ROWS - 1,
+            x: spawn_x,
+            y: config.num_rows - 1,
+            spawn_x,
+            color: PLAYER_COLORS[slot % PLAYER_COLORS.len()],
             shots: Vec::new(),
+            lives: START_LIVES,
+            score: 0,
+            respawn_timer: None,
+            effects: Vec::new(),
+            weapon: weapon::ALL[0],
+            reload: Duration::ZERO,
+            config: *config,
         }
     }
 
     pub fn move_left(&mut self) {
-        if self.x > 0 {
+        if self.x > 0 && !self.is_dead() {
             self.x -= 1;
         }
     }
 
     pub fn move_right(&mut self) {
-        if self.x < NUM_COLS - 1 {
+        if self.x < self.config.num_cols - 1 && !self.is_dead() {
             self.x += 1;
         }
     }
 
     pub fn shoot(&mut self) -> bool {
-        if self.shots.len() < 2 {
-            // Max 2 shots at the time
-            self.shots.push(Shot::new(self.x, self.y - 1));
+        if self.is_dead() || !self.reload.is_zero() {
+            return false;
+        }
+
+        let rapid_fire = self.has_effect(PowerUpKind::RapidFire);
+        let max_shots = self.weapon.max_projectiles() + if rapid_fire { RAPID_FIRE_EXTRA_SHOTS } else { 0 };
+        let columns: Vec<usize> = if self.has_effect(PowerUpKind::Spread) {
+            [self.x.checked_sub(1), Some(self.x), Some(self.x + 1).filter(|x| *x < self.config.num_cols)].into_iter().flatten().collect()
+        } else {
+            vec![self.x]
+        };
+
+        // A spread volley counts as many shots as it fires
+        let volley: Vec<Shot> = columns.iter().flat_map(|x| self.weapon.fire(*x, self.y - 1, &self.config)).collect();
+        if self.shots.len() + volley.len() > max_shots * columns.len() {
+            return false;
+        }
 
-            return true
+        let piercing = self.has_effect(PowerUpKind::Piercing);
+        for mut shot in volley {
+            shot.piercing = piercing;
+            self.shots.push(shot);
+        }
+
+        self.reload = if rapid_fire { self.weapon.cooldown() / 2 } else { self.weapon.cooldown() };
+        true
+    }
+
+    // Moves, fires or switches weapons, returns true when a shot was fired
+    pub fn steer(&mut self, action: Action) -> bool {
+        match action {
+            Action::MoveLeft => self.move_left(),
+            Action::MoveRight => self.move_right(),
+            Action::Fire => return self.shoot(),
+            Action::SwitchWeapon => self.set_weapon(weapon::next(self.weapon)),
+            _ => {}
         }
 
         false
     }
 
+    pub fn weapon(&self) -> &'static dyn Weapon {
+        self.weapon
+    }
+
+    // Shots already fired keep flying
+    pub fn set_weapon(&mut self, weapon: &'static dyn Weapon) {
+        self.weapon = weapon;
+    }
+
+    pub fn has_effect(&self, kind: PowerUpKind) -> bool {
+        self.effects.iter().any(|effect| effect.kind == kind)
+    }
+
+    // Active power-ups and their timers, for the HUD
+    pub fn effects(&self) -> &[Effect] {
+        &self.effects
+    }
+
+    // Catching one that's already active starts its timer over
+    pub fn power_up(&mut self, kind: PowerUpKind) {
+        self.effects.retain(|effect| effect.kind != kind);
+        self.effects.push(Effect { kind, timer: Timer::from_millis(kind.duration().as_millis() as u64) });
+    }
+
+    pub fn x(&self) -> usize {
+        self.x
+    }
+
+    pub fn y(&self) -> usize {
+        self.y
+    }
+
+    pub fn lives(&self) -> u32 {
+        self.lives
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.lives == 0
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.respawn_timer.is_some()
+    }
+
     pub fn update(&mut self, delta: Duration) {
+        self.reload = self.reload.saturating_sub(delta);
+
         for shot in self.shots.iter_mut() {
             shot.update(delta);
         }
 
         self.shots.retain(|shot| !shot.dead()); // Keep only alive rockets
+
+        for effect in self.effects.iter_mut() {
+            effect.timer.update(delta);
+        }
+        self.effects.retain(|effect| !effect.timer.ready);
+
+        if let Some(timer) = self.respawn_timer.as_mut() {
+            timer.update(delta);
+            if timer.ready {
+                self.respawn_timer = None;
+            }
+        }
     }
 
-    pub fn detect_hits(&mut self, invaders: &mut Invaders) -> bool {
-        let mut hit_something = false;
+    // Returns the points scored by killing invaders, shots stopped by a bunker score nothing.
+    // Destroyed invaders may drop a power-up.
+    pub fn detect_hits<R: Rng>(&mut self, invaders: &mut Invaders, bunkers: &mut Bunkers, mystery_ship: &mut MysteryShip, power_ups: &mut PowerUps, rng: &mut R) -> u32 {
+        let mut points = 0;
         for shot in self.shots.iter_mut() {
             if !shot.exploding { // We already hit something
-                if invaders.kill_invader_at(shot.x, shot.y) {
-                    hit_something= true;
+                if bunkers.hit_at(shot.x, shot.y) {
                     shot.explode();
+                } else if let Some(bonus) = mystery_ship.kill_at(shot.x, shot.y) {
+                    points += bonus;
+                    if !shot.piercing {
+                        shot.explode();
+                    }
+                } else if let Some(hit) = invaders.kill_invader_at(shot.x, shot.y) {
+                    if let Hit::Killed(invader_points) = hit {
+                        points += invader_points;
+                        power_ups.maybe_drop(shot.x, shot.y, rng);
+                    }
+                    if !shot.piercing {
+                        shot.explode();
+                    }
                 }
             }
 
         }
 
-        hit_something
+        self.score += points;
+        points
     }
 
+    // Returns true when an enemy shot cost the player a life, the shield stops them without losing one
+    pub fn detect_enemy_hits(&mut self, invaders: &mut Invaders) -> bool {
+        if self.is_dead() || self.is_invulnerable() || !invaders.hit_player_at(self.x, self.y) || self.has_effect(PowerUpKind::Shield) {
+            return false;
+        }
+
+        self.lives -= 1;
+        if !self.is_dead() {
+            // Respawn where we started, with a couple of seconds to get away from the incoming fire
+            self.x = self.spawn_x;
+            self.respawn_timer = Some(Timer::from_millis(2000));
+        }
+
+        true
+    }
 }
 
 impl Drawable for Player {
     fn draw(&self, frame: &mut crate::frame::Frame) {
-        frame[self.x][self.y] = "A";
+        // Blink while invulnerable
+        let visible = match &self.respawn_timer {
+            Some(timer) => (timer.time_left.as_millis() / 100) % 2 == 0,
+            None => !self.is_dead(),
+        };
+        if visible {
+            let mut cell = Cell::new("A").fg(self.color).attr(Attribute::Bold);
+            if self.has_effect(PowerUpKind::Shield) {
+                cell = cell.bg(PowerUpKind::Shield.color());
+            }
+            frame[self.x][self.y] = cell;
+        }
 
         for shot in self.shots.iter() {
             shot.draw(frame);
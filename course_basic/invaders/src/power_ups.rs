@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use crossterm::style::{Attribute, Color};
+use rand::Rng;
+use rusty_time::timer::Timer;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::GameConfig, frame::{Cell, Drawable, Frame}};
+
+// Chance for a destroyed invader to drop a power-up
+const DROP_CHANCE: f64 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerUpKind {
+    RapidFire, // More shots in the air at the same time
+    Spread,    // Three shots side by side
+    Piercing,  // Shots go through invaders
+    Shield,    // Enemy shots don't cost a life
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 4] = [PowerUpKind::RapidFire, PowerUpKind::Spread, PowerUpKind::Piercing, PowerUpKind::Shield];
+
+    // Letter on the falling power-up and in the HUD
+    pub fn glyph(&self) -> &'static str {
+        match self {
+            PowerUpKind::RapidFire => "R",
+            PowerUpKind::Spread => "S",
+            PowerUpKind::Piercing => "P",
+            PowerUpKind::Shield => "O",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            PowerUpKind::RapidFire => Color::Yellow,
+            PowerUpKind::Spread => Color::Cyan,
+            PowerUpKind::Piercing => Color::Magenta,
+            PowerUpKind::Shield => Color::Blue,
+        }
+    }
+
+    // How long it lasts once caught
+    pub fn duration(&self) -> Duration {
+        match self {
+            PowerUpKind::RapidFire => Duration::from_secs(10),
+            PowerUpKind::Spread => Duration::from_secs(8),
+            PowerUpKind::Piercing => Duration::from_secs(8),
+            PowerUpKind::Shield => Duration::from_secs(6),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PowerUp {
+    pub x: usize,
+    pub y: usize,
+    pub kind: PowerUpKind,
+}
+
+// Power-ups falling towards the player row, gone when they reach the bottom uncaught
+#[derive(Serialize, Deserialize)]
+pub struct PowerUps {
+    falling: Vec<PowerUp>,
+    #[serde(with = "crate::save::timer")]
+    fall_timer: Timer,
+    config: GameConfig,
+}
+
+impl PowerUps {
+    pub fn new(config: &GameConfig) -> Self {
+        Self {
+            falling: Vec::new(),
+            fall_timer: Timer::from_millis(200),
+            config: *config,
+        }
+    }
+
+    // Called for every destroyed invader, returns true if it dropped something
+    pub fn maybe_drop<R: Rng>(&mut self, x: usize, y: usize, rng: &mut R) -> bool {
+        if !rng.gen_bool(DROP_CHANCE) {
+            return false;
+        }
+
+        let kind = PowerUpKind::ALL[rng.gen_range(0..PowerUpKind::ALL.len())];
+        self.falling.push(PowerUp { x, y, kind });
+        true
+    }
+
+    pub fn update(&mut self, delta: Duration) {
+        self.fall_timer.update(delta);
+        if self.fall_timer.ready {
+            self.fall_timer.reset();
+            for power_up in self.falling.iter_mut() {
+                power_up.y += 1;
+            }
+            self.falling.retain(|power_up| power_up.y < self.config.num_rows);
+        }
+    }
+
+    // Takes the power-up at the given cell, if there is one
+    pub fn catch_at(&mut self, x: usize, y: usize) -> Option<PowerUpKind> {
+        let idx = self.falling.iter().position(|power_up| power_up.x == x && power_up.y == y)?;
+        Some(self.falling.remove(idx).kind)
+    }
+}
+
+impl Drawable for PowerUps {
+    fn draw(&self, frame: &mut Frame) {
+        for power_up in self.falling.iter() {
+            frame[power_up.x][power_up.y] = Cell::new(power_up.kind.glyph()).fg(power_up.kind.color()).attr(Attribute::Bold);
+        }
+    }
+}
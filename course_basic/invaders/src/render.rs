@@ -1,24 +1,281 @@
-use std::io::{Stdout, Write};
+use std::io::{self, BufWriter, Stdout, Write};
 
-use crossterm::{QueueableCommand, style::{SetBackgroundColor, Color}, terminal::{ClearType, Clear}, cursor::MoveTo};
+use crossterm::{QueueableCommand, style::{Attribute, Attributes, SetAttribute, SetAttributes, SetBackgroundColor, SetForegroundColor, Color, Print}, terminal::{self, ClearType, Clear}, cursor::MoveTo};
 
-use crate::frame::Frame;
+use crate::frame::{glyph, Cell, Frame, BORDER};
 
-pub fn render(stdout: &mut Stdout, last_frame: &Frame, curr_frame: &Frame, force: bool) {
-    if force {
-        stdout.queue(SetBackgroundColor(Color::Blue)).unwrap();
-        stdout.queue(Clear(ClearType::All)).unwrap();
-        stdout.queue(SetBackgroundColor(Color::Black)).unwrap();
+pub trait Renderer {
+    fn render(&mut self, last_frame: &Frame, curr_frame: &Frame, force: bool);
+}
+
+// Colors and attributes the terminal is currently printing with
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Style {
+    fg: Color,
+    bg: Color,
+    attrs: Attributes,
+}
+
+// Whether a terminal of `size` columns and rows has room for a frame and its border
+pub fn fits_terminal(num_cols: usize, num_rows: usize, size: (u16, u16)) -> bool {
+    num_cols + 2 * BORDER <= size.0 as usize && num_rows + 2 * BORDER <= size.1 as usize
+}
+
+// Draws frames on a real terminal through crossterm commands.
+// Changed cells next to each other on a row go out as a single run, with one cursor move and one print.
+pub struct CrosstermRenderer<W: Write> {
+    out: W,
+    style: Option<Style>, // Unknown until the first cell is printed
+    cursor: Option<(usize, usize)>, // Where the next glyph lands without moving the cursor
+    run: String, // Glyphs of the current run, not printed yet
+    changed: Vec<(usize, usize)>, // Row and column of the cells to print, kept to not allocate every frame
+    terminal: Option<(u16, u16)>, // Frames are centered in a terminal of this size, or drawn from the top left corner
+}
+
+impl<W: Write> CrosstermRenderer<W> {
+    pub fn new(out: W) -> Self {
+        Self { out, style: None, cursor: None, run: String::new(), changed: Vec::new(), terminal: None }
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    fn clear(&mut self) {
+        self.out.queue(SetBackgroundColor(Color::Blue)).unwrap();
+        self.out.queue(Clear(ClearType::All)).unwrap();
+        self.style = None;
+        self.cursor = None;
+    }
+
+    // Queues `cell` at (x, y), the cursor only moves when the cell doesn't follow the last one
+    fn put(&mut self, x: usize, y: usize, cell: &Cell) {
+        if self.cursor != Some((x, y)) {
+            self.flush_run();
+            self.out.queue(MoveTo(x as u16, y as u16)).unwrap();
+        }
+        self.set_style(cell);
+        self.run.push_str(cell.glyph);
+        self.cursor = Some((x + 1, y));
+    }
+
+    fn flush_run(&mut self) {
+        if !self.run.is_empty() {
+            self.out.queue(Print(&self.run)).unwrap();
+            self.run.clear();
+        }
+    }
+
+    // Frame around the `num_cols` x `num_rows` cells starting at `origin`, drawn row by row
+    fn draw_border(&mut self, origin: (usize, usize), num_cols: usize, num_rows: usize) {
+        let (left, top) = (origin.0 - BORDER, origin.1 - BORDER);
+        let (right, bottom) = (origin.0 + num_cols, origin.1 + num_rows);
+        let line = |glyph| Cell::new(glyph).fg(Color::White).bg(Color::Blue);
+
+        for (y, (start, middle, end)) in [(top, ("┌", "─", "┐")), (bottom, ("└", "─", "┘"))] {
+            self.put(left, y, &line(start));
+            for x in origin.0..right {
+                self.put(x, y, &line(middle));
+            }
+            self.put(right, y, &line(end));
+        }
+        for y in origin.1..bottom {
+            self.put(left, y, &line("│"));
+            self.put(right, y, &line("│"));
+        }
+    }
+
+    // Shown instead of the frames until the terminal is resized
+    fn draw_too_small(&mut self, size: (u16, u16), num_cols: usize, num_rows: usize) {
+        let lines = [
+            "TERMINAL TOO SMALL".to_string(),
+            format!("NEED {}x{}", num_cols + 2 * BORDER, num_rows + 2 * BORDER),
+        ];
+        let (width, height) = (size.0 as usize, size.1 as usize);
+
+        for (i, line) in lines.iter().enumerate() {
+            let y = (height / 2 + i).saturating_sub(1);
+            let x = width.saturating_sub(line.len()) / 2;
+            for (dx, c) in line.chars().take(width).enumerate() {
+                self.put(x + dx, y, &Cell::new(glyph(c)).fg(Color::Yellow).attr(Attribute::Bold));
+            }
+        }
     }
 
-    for (x, col) in curr_frame.iter().enumerate() {
-        for (y, s) in col.iter().enumerate() {
-            if *s != last_frame[x][y] || force {
-                stdout.queue(MoveTo(x as u16, y as u16)).unwrap();
-                print!("{}", *s);
+    // Only sends the parts of the style that differ from what the terminal already uses
+    fn set_style(&mut self, cell: &Cell) {
+        let current = self.style;
+        let style = Style { fg: cell.fg, bg: cell.bg, attrs: cell.attrs };
+        if current == Some(style) {
+            return;
+        }
+        self.flush_run(); // Printed with the style it was queued with
+
+        if current.map(|style| style.attrs) != Some(cell.attrs) {
+            // Resetting the attributes resets the colors as well
+            self.out.queue(SetAttribute(Attribute::Reset)).unwrap();
+            self.out.queue(SetAttributes(cell.attrs)).unwrap();
+            self.out.queue(SetForegroundColor(cell.fg)).unwrap();
+            self.out.queue(SetBackgroundColor(cell.bg)).unwrap();
+        } else {
+            if current.map(|style| style.fg) != Some(cell.fg) {
+                self.out.queue(SetForegroundColor(cell.fg)).unwrap();
+            }
+            if current.map(|style| style.bg) != Some(cell.bg) {
+                self.out.queue(SetBackgroundColor(cell.bg)).unwrap();
             }
         }
+
+        self.style = Some(style);
     }
+}
 
-    stdout.flush().unwrap();
-}
\ No newline at end of file
+// Room for a whole frame, so it reaches the terminal in a single write
+const STDOUT_BUFFER: usize = 64 * 1024;
+
+impl CrosstermRenderer<BufWriter<Stdout>> {
+    // Follows the size of the terminal, frames are centered in a border
+    pub fn stdout() -> Self {
+        Self {
+            terminal: terminal::size().ok(),
+            ..Self::new(BufWriter::with_capacity(STDOUT_BUFFER, io::stdout()))
+        }
+    }
+}
+
+impl<W: Write> Renderer for CrosstermRenderer<W> {
+    fn render(&mut self, last_frame: &Frame, curr_frame: &Frame, force: bool) {
+        let num_cols = curr_frame.len();
+        let num_rows = curr_frame.first().map(|col| col.len()).unwrap_or(0);
+        let mut force = force;
+        let mut origin = (0, 0);
+        self.cursor = None; // Nothing tells where it was left
+
+        if let Some(size) = self.terminal {
+            // Whatever was on screen is garbage after a resize, everything is drawn again
+            let new_size = terminal::size().unwrap_or(size);
+            force |= new_size != size;
+            self.terminal = Some(new_size);
+
+            if !fits_terminal(num_cols, num_rows, new_size) {
+                if force {
+                    self.clear();
+                    self.draw_too_small(new_size, num_cols, num_rows);
+                    self.flush_run();
+                    self.out.flush().unwrap();
+                }
+                return;
+            }
+
+            origin = ((new_size.0 as usize - num_cols) / 2, (new_size.1 as usize - num_rows) / 2);
+        }
+
+        if force {
+            self.clear();
+            if self.terminal.is_some() {
+                self.draw_border(origin, num_cols, num_rows);
+            }
+        }
+
+        // Found column by column, the way frames are laid out in memory...
+        self.changed.clear();
+        for (x, col) in curr_frame.iter().enumerate() {
+            for (y, cell) in col.iter().enumerate() {
+                if force || *cell != last_frame[x][y] {
+                    self.changed.push((y, x));
+                }
+            }
+        }
+
+        // ...and printed row by row, so that neighbouring cells follow each other
+        self.changed.sort_unstable();
+        for idx in 0..self.changed.len() {
+            let (y, x) = self.changed[idx];
+            self.put(origin.0 + x, origin.1 + y, &curr_frame[x][y]);
+        }
+
+        self.flush_run();
+        self.out.flush().unwrap();
+    }
+}
+
+// Headless backend, keeps the cell grid and the cursor moves in memory instead of drawing them.
+// Like on a terminal, the cursor only moves to start a run of changed cells.
+pub struct MemoryRenderer {
+    grid: Frame,
+    cursor_moves: Vec<(u16, u16)>,
+    renders: usize,
+}
+
+impl MemoryRenderer {
+    pub fn new() -> Self {
+        Self {
+            grid: Frame::new(),
+            cursor_moves: Vec::new(),
+            renders: 0,
+        }
+    }
+
+    pub fn grid(&self) -> &Frame {
+        &self.grid
+    }
+
+    pub fn cursor_moves(&self) -> &[(u16, u16)] {
+        &self.cursor_moves
+    }
+
+    pub fn renders(&self) -> usize {
+        self.renders
+    }
+
+    pub fn clear_cursor_moves(&mut self) {
+        self.cursor_moves.clear();
+    }
+
+    // One line per row, handy for snapshot tests of whole frames
+    pub fn snapshot(&self) -> String {
+        let num_rows = self.grid.first().map(|col| col.len()).unwrap_or(0);
+        let mut lines = Vec::with_capacity(num_rows);
+
+        for y in 0..num_rows {
+            let line: String = self.grid.iter().map(|col| col[y].glyph).collect();
+            lines.push(line);
+        }
+
+        lines.join("\n")
+    }
+}
+
+impl Default for MemoryRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer for MemoryRenderer {
+    fn render(&mut self, last_frame: &Frame, curr_frame: &Frame, force: bool) {
+        let num_rows = curr_frame.first().map(|col| col.len()).unwrap_or(0);
+        let size = |frame: &Frame| (frame.len(), frame.first().map(|col| col.len()).unwrap_or(0));
+
+        // Blank grid as big as the frames we are given, drawn in full, when there's none yet or the size changed
+        let force = force || size(&self.grid) != size(curr_frame) || size(last_frame) != size(curr_frame);
+        if force {
+            self.grid = vec![vec![Cell::default(); num_rows]; curr_frame.len()];
+        }
+
+        let mut cursor = None;
+        for y in 0..num_rows {
+            for (x, col) in curr_frame.iter().enumerate() {
+                if force || col[y] != last_frame[x][y] {
+                    if cursor != Some((x, y)) {
+                        self.cursor_moves.push((x as u16, y as u16));
+                    }
+                    cursor = Some((x + 1, y));
+                    self.grid[x][y] = col[y];
+                }
+            }
+        }
+
+        self.renders += 1;
+    }
+}
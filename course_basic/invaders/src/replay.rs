@@ -0,0 +1,165 @@
+use std::{fs::File, io::{self, BufRead, BufReader, BufWriter, Write}, path::Path, time::Duration, collections::VecDeque};
+
+use crate::{config::GameConfig, highscores::HighScores, input::{Input, Tick}, level::Level, invalid_data};
+
+const HEADER: &str = "invaders-replay 8";
+
+// Writes the board size, the random seed, the level ('-' for the classic formation), whether the autopilot played and
+// whether the recording went straight into a single game instead of through the menus, and the high scores the HUD and
+// the game over screen showed: their count, then one line each. Then one line per tick: the delta in nanoseconds
+// followed by the inputs given
+pub struct Recorder {
+    out: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn create<P: AsRef<Path>>(path: P, config: &GameConfig, seed: u64, level: Option<&Level>, autoplay: bool, single_game: bool, high_scores: &HighScores) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "{}", HEADER)?;
+        writeln!(out, "{}x{}", config.num_cols, config.num_rows)?;
+        writeln!(out, "{}", seed)?;
+        writeln!(out, "{}", level.map(|level| level.to_line()).unwrap_or_else(|| "-".to_string()))?;
+        writeln!(out, "autoplay {}", autoplay as u8)?;
+        writeln!(out, "single_game {}", single_game as u8)?;
+        writeln!(out, "high_scores {}", high_scores.entries().len())?;
+        write!(out, "{}", high_scores.to_text())?;
+
+        Ok(Self { out })
+    }
+
+    pub fn record_tick(&mut self, delta: Duration, inputs: &[Input]) -> io::Result<()> {
+        write!(self.out, "{}", delta.as_nanos())?;
+        for input in inputs {
+            write!(self.out, " {}", input.name())?;
+        }
+        writeln!(self.out)
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+pub struct Replay {
+    config: GameConfig,
+    seed: u64,
+    level: Option<Level>,
+    autoplay: bool,
+    single_game: bool,
+    high_scores: HighScores,
+    ticks: VecDeque<Tick>,
+}
+
+impl Replay {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut lines = reader.lines();
+
+        match lines.next() {
+            Some(Ok(header)) if header == HEADER => {}
+            Some(Err(e)) => return Err(e),
+            _ => return Err(invalid_data(format!("missing '{}' header", HEADER))),
+        }
+
+        let config = lines
+            .next()
+            .transpose()?
+            .and_then(|size| GameConfig::parse_size(&size).ok())
+            .ok_or_else(|| invalid_data("line 2: expected the board size".to_string()))?;
+
+        let seed = lines
+            .next()
+            .transpose()?
+            .and_then(|seed| seed.trim().parse::<u64>().ok())
+            .ok_or_else(|| invalid_data("line 3: expected the random seed".to_string()))?;
+
+        let level = match lines.next().transpose()? {
+            Some(line) if line.trim() == "-" => None,
+            Some(line) => Some(Level::from_line(&line).map_err(|e| invalid_data(format!("line 4: {}", e)))?),
+            None => return Err(invalid_data("line 4: expected the level".to_string())),
+        };
+
+        let autoplay = match lines.next().transpose()?.as_deref() {
+            Some("autoplay 0") => false,
+            Some("autoplay 1") => true,
+            _ => return Err(invalid_data("line 5: expected 'autoplay 0' or 'autoplay 1'".to_string())),
+        };
+
+        let single_game = match lines.next().transpose()?.as_deref() {
+            Some("single_game 0") => false,
+            Some("single_game 1") => true,
+            _ => return Err(invalid_data("line 6: expected 'single_game 0' or 'single_game 1'".to_string())),
+        };
+
+        let num_high_scores = lines
+            .next()
+            .transpose()?
+            .and_then(|line| line.strip_prefix("high_scores ")?.trim().parse::<usize>().ok())
+            .ok_or_else(|| invalid_data("line 7: expected 'high_scores' and their count".to_string()))?;
+        let mut high_scores = String::new();
+        for _ in 0..num_high_scores {
+            let line = lines.next().transpose()?.ok_or_else(|| invalid_data("missing high scores".to_string()))?;
+            high_scores.push_str(&line);
+            high_scores.push('\n');
+        }
+        let high_scores = HighScores::parse(&high_scores)?;
+
+        let mut ticks = VecDeque::new();
+        for (idx, line) in lines.enumerate() {
+            let line = line?;
+            let line_number = idx + 8 + num_high_scores; // Header, board size, seed, level, autoplay, single game and high scores come first
+            let mut fields = line.split_whitespace();
+
+            let delta = fields
+                .next()
+                .and_then(|nanos| nanos.parse::<u64>().ok())
+                .map(Duration::from_nanos)
+                .ok_or_else(|| invalid_data(format!("line {}: expected a tick duration", line_number)))?;
+
+            let mut inputs = Vec::new();
+            for name in fields {
+                let input = Input::from_name(name)
+                    .ok_or_else(|| invalid_data(format!("line {}: unknown input '{}'", line_number, name)))?;
+                inputs.push(input);
+            }
+
+            ticks.push_back(Tick { delta, inputs });
+        }
+
+        Ok(Self { config, seed, level, autoplay, single_game, high_scores, ticks })
+    }
+
+    // The board the recording was made on
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    // Seed of the random generator used during the recording
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Formation the recording was played with, None for the classic one
+    pub fn level(&self) -> Option<&Level> {
+        self.level.as_ref()
+    }
+
+    // The autopilot's actions aren't recorded, it has to play again
+    pub fn autoplay(&self) -> bool {
+        self.autoplay
+    }
+
+    // Headless recordings skip the menus, their replays have to as well
+    pub fn single_game(&self) -> bool {
+        self.single_game
+    }
+
+    // The table as it was during the recording, the replay shows and fills in the same one
+    pub fn high_scores(&self) -> &HighScores {
+        &self.high_scores
+    }
+
+    pub fn next_tick(&mut self) -> Option<Tick> {
+        self.ticks.pop_front()
+    }
+}
@@ -0,0 +1,162 @@
+use std::{fs, io, path::{Path, PathBuf}, time::Duration};
+
+use rusty_time::timer::Timer;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::game::Game;
+use crate::invalid_data;
+
+// Bumped whenever the layout of a saved game changes, files with another version are refused
+pub const SAVE_VERSION: u32 = 4;
+
+#[derive(Serialize, Deserialize)]
+struct SaveFile {
+    version: u32,
+    game: Game,
+}
+
+// Read first, so an incompatible file is reported as such instead of as a missing field
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+// `save.json` in the user's data directory, next to the high scores
+pub fn default_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("invaders").join("save.json"))
+}
+
+pub fn save_game<P: AsRef<Path>>(path: P, game: &Game) -> io::Result<()> {
+    if let Some(dir) = path.as_ref().parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    #[derive(Serialize)]
+    struct SaveFileRef<'a> {
+        version: u32,
+        game: &'a Game,
+    }
+
+    let contents = serde_json::to_string(&SaveFileRef { version: SAVE_VERSION, game })?;
+    fs::write(path, contents)
+}
+
+pub fn load_game<P: AsRef<Path>>(path: P) -> io::Result<Game> {
+    let contents = fs::read_to_string(path)?;
+
+    let header: SaveHeader = serde_json::from_str(&contents)
+        .map_err(|e| invalid_data(format!("not a saved game: {}", e)))?;
+    if header.version != SAVE_VERSION {
+        return Err(invalid_data(format!("saved game version {} isn't supported, expected version {}", header.version, SAVE_VERSION)));
+    }
+
+    let save: SaveFile = serde_json::from_str(&contents)
+        .map_err(|e| invalid_data(format!("corrupted saved game: {}", e)))?;
+    Ok(save.game)
+}
+
+// Once the saved game is continued, so that it's only continued once
+pub fn remove_game<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+// `Timer` comes without serde support, its public fields are all there is to it
+#[derive(Serialize, Deserialize)]
+struct TimerState {
+    duration: Duration,
+    time_left: Duration,
+    ready: bool,
+}
+
+impl From<&Timer> for TimerState {
+    fn from(timer: &Timer) -> Self {
+        Self { duration: timer.duration, time_left: timer.time_left, ready: timer.ready }
+    }
+}
+
+impl From<TimerState> for Timer {
+    fn from(state: TimerState) -> Self {
+        Self { duration: state.duration, time_left: state.time_left, ready: state.ready }
+    }
+}
+
+// For `#[serde(with = "crate::save::timer")]` on `Timer` fields
+pub(crate) mod timer {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(timer: &Timer, serializer: S) -> Result<S::Ok, S::Error> {
+        TimerState::from(timer).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Timer, D::Error> {
+        TimerState::deserialize(deserializer).map(Timer::from)
+    }
+}
+
+// Same for `Option<Timer>` fields
+pub(crate) mod optional_timer {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(timer: &Option<Timer>, serializer: S) -> Result<S::Ok, S::Error> {
+        timer.as_ref().map(TimerState::from).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Timer>, D::Error> {
+        Ok(Option::<TimerState>::deserialize(deserializer)?.map(Timer::from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, time::Duration};
+
+    use super::*;
+    use crate::{bindings::Action, config::GameConfig, input::TICK};
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("invaders-save-{}-{}.json", name, std::process::id()))
+    }
+
+    #[test]
+    fn round_trips_a_game_in_progress() {
+        let path = temp_path("round-trip");
+        let mut game = Game::new(&GameConfig::default(), 42);
+        game.players[0].steer(Action::SwitchWeapon);
+        game.players[0].steer(Action::Fire);
+        // Long enough for the army to turn around at the edge, with timers part way through
+        for _ in 0..(Duration::from_secs(5).as_nanos() / TICK.as_nanos()) {
+            game.update(TICK);
+        }
+
+        save_game(&path, &game).unwrap();
+        let loaded = load_game(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.players[0].weapon().name(), "MISSILE");
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), serde_json::to_string(&game).unwrap());
+    }
+
+    #[test]
+    fn refuses_other_versions() {
+        let path = temp_path("version");
+        fs::write(&path, r#"{"version": 1, "game": {}}"#).unwrap();
+        let err = load_game(&path).err().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), format!("saved game version 1 isn't supported, expected version {}", SAVE_VERSION));
+    }
+
+    #[test]
+    fn reports_files_that_arent_saves() {
+        let path = temp_path("not-json");
+        fs::write(&path, "AAA 100\n").unwrap();
+        let err = load_game(&path).err().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(err.to_string().starts_with("not a saved game"), "{}", err);
+    }
+}
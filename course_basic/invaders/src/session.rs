@@ -0,0 +1,329 @@
+use std::{io, path::PathBuf, time::Duration};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    audio::{Sound, SoundSink},
+    autopilot::Autopilot,
+    bindings::{Action, KeyBindings},
+    config::GameConfig,
+    frame::{clear_frame, new_frame, new_hud, stack_into, Drawable, Frame},
+    game::Game,
+    highscores::HighScores,
+    hud::Hud,
+    input::Input,
+    level::Level,
+    menu::{DemoBanner, GameOver, GameOverScreen, GameState, PauseMenu, SavePrompt, TitleScreen},
+    save,
+};
+
+// How long the title screen waits for a key before the autopilot starts a demo game
+const ATTRACT_DELAY: Duration = Duration::from_secs(10);
+
+// The menus and the games played from them, one tick at a time, until the player quits
+pub struct Session {
+    pub state: GameState,
+    pub game: Game,
+    pub high_scores: HighScores,
+    config: GameConfig,
+    level: Option<Level>,
+    num_players: usize,
+    bindings: KeyBindings, // Only for the menus, the inputs come already translated
+    rng: StdRng, // Every game is seeded from it, so a replay only needs its seed
+    autopilot: Autopilot,
+    autoplay: bool, // The autopilot plays instead of the first player
+    single_game: bool, // Straight into a game, quitting once it's over
+    high_scores_path: Option<PathBuf>,
+    save_path: Option<PathBuf>,
+    persist: bool, // Whether saves and high scores are written to their paths
+    resumed: bool, // The game comes from `save_path`, the file stays until it's continued or saved again
+    idle: Duration, // Time spent on the title screen without a key press
+    quit: bool,
+}
+
+impl Session {
+    // On the title screen, with an empty high score table and nowhere to save a game
+    pub fn new(config: &GameConfig, level: Option<Level>, num_players: usize, seed: u64, bindings: KeyBindings) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        Self {
+            state: GameState::Title,
+            game: new_game(config, level.as_ref(), num_players, rng.gen()),
+            high_scores: HighScores::default(),
+            config: *config,
+            level,
+            num_players,
+            bindings,
+            rng,
+            autopilot: Autopilot::new(),
+            autoplay: false,
+            single_game: false,
+            high_scores_path: None,
+            save_path: None,
+            persist: true,
+            resumed: false,
+            idle: Duration::ZERO,
+            quit: false,
+        }
+    }
+
+    pub fn with_high_scores(mut self, high_scores: HighScores, path: Option<PathBuf>) -> Self {
+        self.high_scores = high_scores;
+        self.high_scores_path = path;
+        self
+    }
+
+    // Single player games can then be saved when quitting
+    pub fn with_save_path(mut self, path: Option<PathBuf>) -> Self {
+        self.save_path = path;
+        self
+    }
+
+    // Without, the menus stay the same but nothing is written, e.g. for replays
+    pub fn with_persist(mut self, persist: bool) -> Self {
+        self.persist = persist;
+        self
+    }
+
+    pub fn with_autoplay(mut self, autoplay: bool) -> Self {
+        self.autoplay = autoplay;
+        self
+    }
+
+    // A resumed game waits on the pause menu until the player is ready
+    pub fn resume(mut self, game: Game) -> Self {
+        self.game = game;
+        self.state = GameState::Paused;
+        self.resumed = true;
+        self
+    }
+
+    // For headless runs, with nobody to go through the menus
+    pub fn single_game(mut self) -> Self {
+        self.single_game = true;
+        self.state = GameState::Playing;
+        self
+    }
+
+    pub fn has_quit(&self) -> bool {
+        self.quit
+    }
+
+    // Printable keys are text instead of actions while the initials of a new high score are typed in
+    pub fn is_typing(&self) -> bool {
+        matches!(&self.state, GameState::GameOver(GameOver { entry: Some(_), .. }))
+    }
+
+    // Quitting without the menus, e.g. while the terminal is too small to show them. A single player game in progress
+    // is saved, instead of asking whether to.
+    pub fn leave(&mut self) -> io::Result<()> {
+        let in_progress = matches!(self.state, GameState::Playing | GameState::Paused | GameState::SavePrompt);
+        if let (true, 1, true, Some(path)) = (in_progress, self.num_players, self.persist, &self.save_path) {
+            save::save_game(path, &self.game)?;
+        }
+        self.quit = true;
+        Ok(())
+    }
+
+    // The saved game this one was resumed from is gone once it goes on, or the player drops it
+    fn remove_save(&mut self) -> io::Result<()> {
+        if let (true, true, Some(path)) = (self.resumed, self.persist, &self.save_path) {
+            save::remove_game(path)?;
+        }
+        self.resumed = false;
+        Ok(())
+    }
+
+    fn start_game(&mut self) {
+        self.game = new_game(&self.config, self.level.as_ref(), self.num_players, self.rng.gen());
+        self.state = GameState::Playing;
+    }
+
+    // One step of the simulation: the inputs go to the menus or the first player, `remote` to the second one
+    pub fn tick(&mut self, delta: Duration, inputs: &[Input], remote: &[Action], audio: &mut dyn SoundSink) -> io::Result<()> {
+        if !inputs.is_empty() {
+            self.idle = Duration::ZERO;
+        }
+
+        for input in inputs {
+            self.handle(*input, audio)?;
+            if self.quit {
+                return Ok(());
+            }
+        }
+
+        // Attract mode
+        if let GameState::Title = self.state {
+            self.idle += delta;
+            if self.idle >= ATTRACT_DELAY {
+                self.idle = Duration::ZERO;
+                self.game = new_game(&self.config, self.level.as_ref(), self.num_players, self.rng.gen());
+                self.state = GameState::Demo;
+            }
+        }
+
+        // Updates
+        if let GameState::Playing | GameState::Demo = self.state {
+            let demo = matches!(self.state, GameState::Demo);
+
+            if self.autoplay || demo {
+                if let Some(action) = self.autopilot.next_action(delta, &self.game.players[0], &self.game.invaders) {
+                    if self.game.players[0].steer(action) && !demo {
+                        audio.play(Sound::Pew);
+                    }
+                }
+            }
+
+            // The remote player can only move and fire, the menus and the demo belong to the host. Whatever they pressed
+            // meanwhile is dropped, it would otherwise all happen at the start of the next game.
+            if !demo {
+                for action in remote {
+                    if self.game.players[1].steer(*action) {
+                        audio.play(Sound::Pew);
+                    }
+                }
+            }
+
+            let sounds = self.game.update(delta);
+            if !demo {
+                for sound in sounds {
+                    audio.play(sound);
+                }
+            }
+
+            if demo && self.game.is_over() {
+                self.state = GameState::Title;
+            } else if self.game.is_over() {
+                audio.play(Sound::Lose);
+                match self.single_game {
+                    true => self.quit = true,
+                    false => self.state = GameState::GameOver(GameOver::new(self.game.score(), &self.high_scores)),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle(&mut self, input: Input, audio: &mut dyn SoundSink) -> io::Result<()> {
+        match &mut self.state {
+            GameState::Title => match input {
+                Input::Action(Action::Quit) => self.quit = true,
+                Input::Action(Action::Fire) | Input::Enter => self.start_game(),
+                _ => {}
+            },
+            GameState::Playing => match input {
+                // Single player games can be saved for later
+                Input::Action(Action::Quit) if self.num_players == 1 && self.save_path.is_some() => self.state = GameState::SavePrompt,
+                Input::Action(Action::Quit) => {
+                    audio.play(Sound::Lose);
+                    self.state = GameState::Title;
+                }
+                Input::Action(Action::Pause) => self.state = GameState::Paused,
+                Input::Action(action) if !self.autoplay && self.game.players[0].steer(action) => audio.play(Sound::Pew),
+                _ => {}
+            },
+            GameState::SavePrompt => match input {
+                Input::Text('y' | 'Y') => {
+                    if let (true, Some(path)) = (self.persist, &self.save_path) {
+                        save::save_game(path, &self.game)?;
+                    }
+                    self.resumed = false;
+                    self.state = GameState::Title;
+                }
+                Input::Text('n' | 'N') => {
+                    self.remove_save()?;
+                    audio.play(Sound::Lose);
+                    self.state = GameState::Title;
+                }
+                Input::Action(Action::Quit) => {
+                    self.remove_save()?;
+                    self.state = GameState::Playing;
+                }
+                _ => {}
+            },
+            // Any key ends the demo
+            GameState::Demo => self.state = GameState::Title,
+            GameState::Paused => match input {
+                Input::Action(Action::Pause) | Input::Enter => {
+                    self.remove_save()?;
+                    self.state = GameState::Playing;
+                }
+                // Same as quitting while playing, a game that was saved shouldn't be lost on the way out either
+                Input::Action(Action::Quit) if self.num_players == 1 && self.save_path.is_some() => self.state = GameState::SavePrompt,
+                Input::Action(Action::Quit) => self.state = GameState::Title,
+                _ => {}
+            },
+            GameState::GameOver(game_over) => match game_over.entry.as_mut() {
+                // Typing in the initials of a new high score
+                Some(entry) => match input {
+                    Input::Enter if entry.is_complete() => {
+                        self.high_scores.insert(entry.initials(), game_over.score);
+                        if let (true, Some(path)) = (self.persist, &self.high_scores_path) {
+                            self.high_scores.save(path)?;
+                        }
+                        game_over.entry = None;
+                    }
+                    Input::Action(Action::Quit) => game_over.entry = None,
+                    Input::Backspace => entry.pop(),
+                    Input::Text(c) => entry.push(c),
+                    _ => {}
+                },
+                None => match input {
+                    Input::Action(Action::Fire) | Input::Enter => self.start_game(),
+                    Input::Action(Action::Quit) => self.state = GameState::Title,
+                    _ => {}
+                },
+            },
+        }
+
+        Ok(())
+    }
+
+    // The HUD, then whatever the current state shows on the board
+    pub fn draw(&self, canvas: &mut Canvas, screen: &mut Frame) {
+        let hud = self.game.hud(self.high_scores.best());
+        let bindings = &self.bindings;
+
+        match &self.state {
+            GameState::Title => canvas.compose(&hud, &[&TitleScreen { high_scores: &self.high_scores, bindings }], screen),
+            GameState::Playing => canvas.compose(&hud, &[&self.game], screen),
+            GameState::Demo => canvas.compose(&hud, &[&self.game, &DemoBanner], screen),
+            GameState::Paused => canvas.compose(&hud, &[&self.game, &PauseMenu { bindings }], screen),
+            GameState::SavePrompt => canvas.compose(&hud, &[&self.game, &SavePrompt { bindings }], screen),
+            GameState::GameOver(game_over) => canvas.compose(&hud, &[&GameOverScreen { game_over, high_scores: &self.high_scores, bindings }], screen),
+        }
+    }
+}
+
+fn new_game(config: &GameConfig, level: Option<&Level>, num_players: usize, seed: u64) -> Game {
+    let game = Game::with_players(config, seed, num_players);
+    match level {
+        Some(level) => game.with_level(level.clone()),
+        None => game,
+    }
+}
+
+// What the HUD and the board are drawn on, kept from one frame to the next
+pub struct Canvas {
+    hud: Frame,
+    board: Frame,
+}
+
+impl Canvas {
+    pub fn new(config: &GameConfig) -> Self {
+        Self { hud: new_hud(config), board: new_frame(config) }
+    }
+
+    // HUD on top, then the board with everything drawn on it
+    fn compose(&mut self, hud: &Hud, drawables: &[&dyn Drawable], screen: &mut Frame) {
+        clear_frame(&mut self.hud);
+        hud.draw(&mut self.hud);
+
+        clear_frame(&mut self.board);
+        for drawable in drawables {
+            drawable.draw(&mut self.board);
+        }
+
+        stack_into(&self.hud, &self.board, screen);
+    }
+}
@@ -1,32 +1,109 @@
 use std::time::Duration;
 
+use crossterm::style::{Attribute, Color};
 use rusty_time::timer::Timer;
+use serde::{Deserialize, Serialize};
 
-use crate::frame::Drawable;
+use crate::frame::{Cell, Drawable};
 
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    Down,
+}
+
+// Missiles speed up by this much every row, until they move one row per `MIN_STEP`
+const ACCELERATION: f64 = 0.7;
+const MIN_STEP: Duration = Duration::from_millis(15);
+
+// How a shot moves from one row to the next
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Trajectory {
+    Straight,
+    Accelerating,
+    // One column sideways every row, bouncing off the sides of the board
+    ZigZag { dx: i32, max_x: usize },
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Shot {
     pub x: usize,
     pub y: usize,
     pub exploding: bool,
+    pub piercing: bool, // Goes through invaders instead of exploding on them
+    direction: Direction,
+    trajectory: Trajectory,
+    bottom: usize,
+    out_of_board: bool,
+    #[serde(with = "crate::save::timer")]
     timer: Timer
 }
 
 impl Shot {
+    // Straight up, one row every 50ms
     pub fn new(x: usize, y: usize) -> Self {
         Self { 
             x, 
             y,
             exploding: false, 
+            piercing: false,
+            direction: Direction::Up,
+            trajectory: Trajectory::Straight,
+            bottom: 0,
+            out_of_board: false,
             timer: Timer::from_millis(50),
         }
     }
 
+    // Enemy fire, falls until it reaches the `bottom` row
+    pub fn new_downward(x: usize, y: usize, bottom: usize) -> Self {
+        Self {
+            direction: Direction::Down,
+            bottom,
+            timer: Timer::from_millis(100),
+            ..Self::new(x, y)
+        }
+    }
+
+    // Time it takes to move one row
+    pub fn with_step(mut self, step: Duration) -> Self {
+        self.timer = Timer::from_millis(step.as_millis() as u64);
+        self
+    }
+
+    pub fn with_trajectory(mut self, trajectory: Trajectory) -> Self {
+        self.trajectory = trajectory;
+        self
+    }
+
+    pub fn trajectory(&self) -> Trajectory {
+        self.trajectory
+    }
+
     pub fn update(&mut self, delta: Duration) {
         self.timer.update(delta);
         if self.timer.ready && !self.exploding {
-            if self.y > 0 {
-                self.y -= 1;
+            match self.direction {
+                Direction::Up if self.y > 0 => self.y -= 1,
+                Direction::Down if self.y < self.bottom => self.y += 1,
+                _ => self.out_of_board = true, // Already on the edge, next step leaves the board
+            }
+
+            match &mut self.trajectory {
+                Trajectory::Straight => {}
+                Trajectory::Accelerating => {
+                    let step = self.timer.duration.mul_f64(ACCELERATION).max(MIN_STEP);
+                    self.timer = Timer::from_millis(step.as_millis() as u64);
+                }
+                Trajectory::ZigZag { dx, max_x } => {
+                    if (self.x == 0 && *dx < 0) || (self.x >= *max_x && *dx > 0) {
+                        *dx = -*dx;
+                    }
+                    self.x = (self.x as i32 + *dx) as usize;
+                    *dx = -*dx;
+                }
             }
+            self.timer.reset();
         }
     }
 
@@ -36,12 +113,24 @@ impl Shot {
     }
 
     pub fn dead(&self) -> bool {
-        (self.exploding && self.timer.ready) || (self.y == 0)
+        (self.exploding && self.timer.ready) || self.out_of_board
     }
 }
 
 impl Drawable for Shot {
     fn draw(&self, frame: &mut crate::frame::Frame) {
-        frame[self.x][self.y] = if self.exploding { "*" } else { "|" };
+        let glyph = match self.trajectory {
+            Trajectory::Straight => "|",
+            Trajectory::Accelerating => "^",
+            Trajectory::ZigZag { dx, .. } if dx > 0 => "/",
+            Trajectory::ZigZag { .. } => "\\",
+        };
+
+        frame[self.x][self.y] = match (self.exploding, self.direction) {
+            (true, _) => Cell::new("*").fg(Color::Yellow).attr(Attribute::Bold),
+            (false, Direction::Up) if self.piercing => Cell::new(glyph).fg(Color::Magenta).attr(Attribute::Bold),
+            (false, Direction::Up) => Cell::new(glyph).fg(Color::White),
+            (false, Direction::Down) => Cell::new("!").fg(Color::Red),
+        };
     }
 }
\ No newline at end of file
@@ -0,0 +1,112 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{config::GameConfig, shot::{Shot, Trajectory}};
+
+// Decides how a player fires. Weapons keep no state of their own, the player keeps track of the
+// cooldown and of the shots in flight, so a new weapon only needs an entry in `ALL`.
+pub trait Weapon {
+    // Unique, it's how saved games refer to the weapon
+    fn name(&self) -> &'static str;
+
+    // Time before the next shot once fired
+    fn cooldown(&self) -> Duration;
+
+    // Shots of this weapon in the air at the same time
+    fn max_projectiles(&self) -> usize;
+
+    // Shots fired from the given cell
+    fn fire(&self, x: usize, y: usize, config: &GameConfig) -> Vec<Shot>;
+}
+
+// Fast and straight, what players started with
+pub struct Laser;
+
+impl Weapon for Laser {
+    fn name(&self) -> &'static str {
+        "LASER"
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    fn max_projectiles(&self) -> usize {
+        2
+    }
+
+    fn fire(&self, x: usize, y: usize, _config: &GameConfig) -> Vec<Shot> {
+        vec![Shot::new(x, y)]
+    }
+}
+
+// Slow to leave, then faster and faster. One at a time.
+pub struct Missile;
+
+impl Weapon for Missile {
+    fn name(&self) -> &'static str {
+        "MISSILE"
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_millis(400)
+    }
+
+    fn max_projectiles(&self) -> usize {
+        1
+    }
+
+    fn fire(&self, x: usize, y: usize, _config: &GameConfig) -> Vec<Shot> {
+        vec![Shot::new(x, y).with_step(Duration::from_millis(150)).with_trajectory(Trajectory::Accelerating)]
+    }
+}
+
+// Wiggles over two columns on its way up
+pub struct ZigZag;
+
+impl Weapon for ZigZag {
+    fn name(&self) -> &'static str {
+        "ZIGZAG"
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_millis(150)
+    }
+
+    fn max_projectiles(&self) -> usize {
+        3
+    }
+
+    fn fire(&self, x: usize, y: usize, config: &GameConfig) -> Vec<Shot> {
+        let trajectory = Trajectory::ZigZag { dx: 1, max_x: config.num_cols - 1 };
+        vec![Shot::new(x, y).with_step(Duration::from_millis(70)).with_trajectory(trajectory)]
+    }
+}
+
+// Every weapon, in the order the switch key goes through them
+pub const ALL: [&dyn Weapon; 3] = [&Laser, &Missile, &ZigZag];
+
+pub fn by_name(name: &str) -> Option<&'static dyn Weapon> {
+    ALL.iter().find(|weapon| weapon.name() == name).copied()
+}
+
+// The one after `weapon`, back to the first after the last
+pub fn next(weapon: &dyn Weapon) -> &'static dyn Weapon {
+    let idx = ALL.iter().position(|other| other.name() == weapon.name()).unwrap_or(0);
+    ALL[(idx + 1) % ALL.len()]
+}
+
+// For `#[serde(with = "crate::weapon::by_name_serde")]`, weapons are saved by name
+pub(crate) mod by_name_serde {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(weapon: &&'static dyn Weapon, serializer: S) -> Result<S::Ok, S::Error> {
+        weapon.name().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<&'static dyn Weapon, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        by_name(&name).ok_or_else(|| serde::de::Error::custom(format!("unknown weapon '{}'", name)))
+    }
+}
@@ -0,0 +1,141 @@
+use std::{env, fs, path::PathBuf};
+
+use invaders::{audio::SilentSink, bindings::{Action, KeyBindings}, config::GameConfig, game::Game, input::{InputSource, ScriptedInput, Tick, TICK}, menu::GameState, save, session::Session};
+
+fn new_session() -> Session {
+    Session::new(&GameConfig::default(), None, 1, 42, KeyBindings::default())
+}
+
+// A single player session driven by `script` until it runs out, the way the game loop drives one
+fn play(script: &str) -> Session {
+    drive(new_session(), script)
+}
+
+fn drive(mut session: Session, script: &str) -> Session {
+    let mut input = ScriptedInput::parse(script).unwrap();
+
+    while let Some(ticks) = input.next_ticks(session.is_typing()).unwrap() {
+        for Tick { delta, inputs } in ticks {
+            session.tick(delta, &inputs, &[], &mut SilentSink).unwrap();
+            if session.has_quit() {
+                return session;
+            }
+        }
+    }
+
+    session
+}
+
+#[test]
+fn starts_from_the_title_screen() {
+    assert!(matches!(play("").state, GameState::Title));
+    assert!(matches!(play("enter").state, GameState::Playing));
+    assert!(play("quit").has_quit());
+}
+
+#[test]
+fn moves_one_column_per_press() {
+    let start = play("enter").game.players[0].x();
+
+    assert_eq!(play("enter, move left 3").game.players[0].x(), start - 3);
+    assert_eq!(play("enter, move left 3, move right").game.players[0].x(), start - 2);
+}
+
+#[test]
+fn shots_hit_the_invaders_above() {
+    let session = play("enter, fire, wait 1s");
+
+    assert_eq!(session.game.score(), 10); // A drone from the bottom row
+    assert_eq!(session.game.players[0].lives(), 3);
+}
+
+#[test]
+fn switches_weapons() {
+    assert_eq!(play("enter").game.players[0].weapon().name(), "LASER");
+    assert_eq!(play("enter, switch weapon").game.players[0].weapon().name(), "MISSILE");
+}
+
+#[test]
+fn nothing_moves_while_paused() {
+    let start = play("enter").game.players[0].x();
+    let session = play("enter, pause, move left 3");
+
+    assert!(matches!(session.state, GameState::Paused));
+    assert_eq!(session.game.players[0].x(), start);
+    assert!(matches!(play("enter, pause, enter").state, GameState::Playing));
+}
+
+#[test]
+fn quitting_without_a_save_path_goes_back_to_the_title() {
+    assert!(matches!(play("enter, quit").state, GameState::Title));
+}
+
+#[test]
+fn demo_starts_when_idle_and_ends_on_a_key() {
+    assert!(matches!(play("wait 10s").state, GameState::Demo));
+    assert!(matches!(play("wait 10s, fire").state, GameState::Title));
+}
+
+#[test]
+fn remote_player_only_plays_in_games() {
+    let config = GameConfig::default();
+    let mut session = drive(Session::new(&config, None, 2, 42, KeyBindings::default()), "wait 10s");
+    assert!(matches!(session.state, GameState::Demo));
+
+    let x = session.game.players[1].x();
+    session.tick(TICK, &[], &[Action::MoveLeft], &mut SilentSink).unwrap();
+    assert_eq!(session.game.players[1].x(), x);
+
+    let mut session = drive(Session::new(&config, None, 2, 42, KeyBindings::default()), "enter");
+    let x = session.game.players[1].x();
+    session.tick(TICK, &[], &[Action::MoveLeft], &mut SilentSink).unwrap();
+    assert_eq!(session.game.players[1].x(), x - 1);
+}
+
+// A game saved at a path of its own, as `--resume` finds it
+fn resumed_session(name: &str) -> (Session, PathBuf) {
+    let path = env::temp_dir().join(format!("invaders-test-{}-{}.json", name, std::process::id()));
+    save::save_game(&path, &Game::new(&GameConfig::default(), 7)).unwrap();
+    let game = save::load_game(&path).unwrap();
+
+    (new_session().with_save_path(Some(path.clone())).resume(game), path)
+}
+
+#[test]
+fn resumed_games_are_kept_until_continued() {
+    let (session, path) = resumed_session("quit");
+    let session = drive(session, "quit");
+    assert!(matches!(session.state, GameState::SavePrompt));
+    assert!(path.exists());
+    fs::remove_file(path).unwrap();
+
+    let (session, path) = resumed_session("continue");
+    drive(session, "pause");
+    assert!(!path.exists());
+
+    let (session, path) = resumed_session("save");
+    drive(session, "quit, type y");
+    assert!(path.exists());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn leaving_saves_the_game_in_progress() {
+    let path = env::temp_dir().join(format!("invaders-test-leave-{}.json", std::process::id()));
+
+    let mut session = drive(new_session().with_save_path(Some(path.clone())), "");
+    session.leave().unwrap();
+    assert!(session.has_quit());
+    assert!(!path.exists()); // Nothing to save on the title screen
+
+    let mut session = drive(new_session().with_save_path(Some(path.clone())), "enter, move left 3");
+    session.leave().unwrap();
+    assert_eq!(save::load_game(&path).unwrap().players[0].x(), session.game.players[0].x());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn rejects_unknown_steps() {
+    assert!(ScriptedInput::parse("jump").is_err());
+    assert!(ScriptedInput::parse("wait soon").is_err());
+}
@@ -0,0 +1,93 @@
+use invaders::{audio::SilentSink, bindings::KeyBindings, config::GameConfig, frame::Frame, input::{InputSource, ScriptedInput, Tick}, render::{MemoryRenderer, Renderer}, session::{Canvas, Session}};
+
+// The screen of a single player session driven by `script`, as it's sent to the renderer
+fn screen(script: &str) -> Frame {
+    let config = GameConfig::default();
+    let mut session = Session::new(&config, None, 1, 42, KeyBindings::default());
+    let mut input = ScriptedInput::parse(script).unwrap();
+    while let Some(ticks) = input.next_ticks(session.is_typing()).unwrap() {
+        for Tick { delta, inputs } in ticks {
+            session.tick(delta, &inputs, &[], &mut SilentSink).unwrap();
+        }
+    }
+
+    let mut frame = Frame::new();
+    session.draw(&mut Canvas::new(&config), &mut frame);
+    frame
+}
+
+// Snapshot lines without their trailing blanks, so the expected screens can be written down as they look
+fn snapshot(renderer: &MemoryRenderer) -> String {
+    renderer.snapshot().lines().map(str::trim_end).collect::<Vec<_>>().join("\n")
+}
+
+#[test]
+fn title_screen() {
+    let mut renderer = MemoryRenderer::new();
+    renderer.render(&Frame::new(), &screen(""), false);
+
+    assert_eq!(snapshot(&renderer), "\
+SCORE 0 HI 0 WAVE 1 LASER            ♥♥♥
+
+
+
+
+
+
+
+             SPACE INVADERS
+
+
+             ENTER  PLAY
+             TAB    WEAPON
+             P      PAUSE
+             ESC    QUIT
+
+
+
+
+
+");
+}
+
+#[test]
+fn first_frame_of_a_game() {
+    let mut renderer = MemoryRenderer::new();
+    renderer.render(&Frame::new(), &screen("enter"), false);
+
+    assert_eq!(snapshot(&renderer), "\
+SCORE 0 HI 0 WAVE 1 LASER            ♥♥♥
+
+
+  w w w w w w w w w w w w w w w w w w
+
+  o o o o o o o o o o o o o o o o o o
+
+  v v v v v v v v v v v v v v v v v v
+
+  x x x x x x x x x x x x x x x x x x
+
+
+
+
+
+
+
+   ####      ####      ####      ####
+   ####      ####      ####      ####
+
+                    A");
+}
+
+#[test]
+fn only_changed_cells_are_drawn() {
+    let mut renderer = MemoryRenderer::new();
+    let last_frame = screen("enter");
+    renderer.render(&Frame::new(), &last_frame, false);
+    renderer.clear_cursor_moves();
+
+    // The player and the blank it leaves behind are next to each other, a single run
+    renderer.render(&last_frame, &screen("enter, move left"), false);
+    assert_eq!(renderer.cursor_moves(), &[(19, 20)]);
+    assert_eq!(renderer.renders(), 2);
+}